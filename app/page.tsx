@@ -11,6 +11,22 @@ import {
 } from "lucide-react";
 import { useTranslations } from "next-intl";
 import { isTauri } from "@/lib/utils";
+import { getErrorMessage, isCommandError } from "@/lib/errors";
+import {
+  checkFileExists,
+  getOutputTemplate,
+  loadLocaleFile,
+  loadProjectConfig,
+  resolveOutputPath,
+  unwatchSourceFile,
+  watchSourceFile,
+  writeLocaleFile,
+  type JsonErrorLocation,
+  type LoadedLocaleFile,
+  type ProjectConfig,
+  type SourceChange,
+} from "@/lib/locale-files";
+import { recentAdd, type RecentEntry } from "@/lib/recent-files";
 import { getKey, migrateFromLocalStorage } from "@/lib/secure-keys";
 import { UnifiedTranslator, getProviderForModel } from "@/lib/llm";
 import { getAvailableModels, type ModelInfo } from "@/lib/models";
@@ -72,11 +88,17 @@ export default function HomePage() {
   const t = useTranslations();
   const [sourceFilePath, setSourceFilePath] = useState<string | null>(null);
   const [jsonContent, setJsonContent] = useState<any>(null);
+  // Notes for the translator by key path, e.g. ARB descriptions
+  const [keyContext, setKeyContext] = useState<Record<string, string>>({});
   const [excludedPaths, setExcludedPaths] = useState<string[]>([]);
   const [selectedLanguages, setSelectedLanguages] = useState<string[]>([]);
   const [sourceLanguageCode, setSourceLanguageCode] = useState<string | null>(
     null
   );
+  // Settings from a localekit.toml next to the source file, if any
+  const [projectConfig, setProjectConfig] = useState<ProjectConfig | null>(
+    null
+  );
   const [error, setError] = useState<string>("");
   const [isLoading, setIsLoading] = useState(false);
   const [isAboutOpen, setIsAboutOpen] = useState(false);
@@ -147,11 +169,16 @@ export default function HomePage() {
   }, []);
 
   const handleReset = () => {
+    if (isTauri()) {
+      unwatchSourceFile().catch(console.error);
+    }
     setSourceFilePath(null);
     setJsonContent(null);
+    setKeyContext({});
     setExcludedPaths([]);
     setSelectedLanguages([]);
     setSourceLanguageCode(null);
+    setProjectConfig(null);
     setError("");
     setIsTranslating(false);
     setTranslationResults([]);
@@ -164,14 +191,17 @@ export default function HomePage() {
     });
   };
 
-  const handleSelectFile = async () => {
+  // Opens `recentPath` directly if given, otherwise asks with a dialog
+  const handleSelectFile = async (recentPath?: string) => {
     try {
       setIsLoading(true);
       setError("");
       setJsonContent(null);
+      setKeyContext({});
       setExcludedPaths([]);
       setTranslationResults([]);
       setSourceLanguageCode(null);
+      setProjectConfig(null);
       setIsTranslating(false);
       setTranslationProgress({
         currentLanguage: null,
@@ -182,7 +212,8 @@ export default function HomePage() {
       });
 
       // Get file path (no timeout needed - dialog will return null if cancelled)
-      const filePath = await invoke<string | null>("select_source_file");
+      const filePath =
+        recentPath ?? (await invoke<string | null>("select_source_file"));
 
       if (!filePath) {
         setIsLoading(false);
@@ -192,9 +223,9 @@ export default function HomePage() {
 
       setSourceFilePath(filePath);
 
-      // Extract language code from filename if it matches pattern {name}_{langCode}.json
+      // Extract language code from filename if it matches pattern {name}_{langCode}.{ext}
       const fileName = filePath.split(/[/\\]/).pop() || "";
-      const fileNameWithoutExt = fileName.replace(/\.json$/i, "");
+      const fileNameWithoutExt = fileName.replace(/\.[^.]+$/, "");
       const allLanguages = getAllLanguages();
 
       // Check if filename ends with a known language code pattern
@@ -221,26 +252,58 @@ export default function HomePage() {
       }
 
       // Read the file with timeout
-      const readPromise = invoke<string>("read_json_file", { path: filePath });
-      const readTimeoutPromise = new Promise<string>(
+      const readPromise = loadLocaleFile(filePath);
+      const readTimeoutPromise = new Promise<LoadedLocaleFile>(
         (_, reject) =>
           setTimeout(() => reject(new Error("File read timeout")), 10000) // 10 second timeout
       );
 
-      const content = await Promise.race([readPromise, readTimeoutPromise]);
-
       // Parse JSON
       try {
-        const parsed = JSON.parse(content);
-        setJsonContent(parsed);
+        const { data, encoding, warnings, context } = await Promise.race([
+          readPromise,
+          readTimeoutPromise,
+        ]);
+        console.log(
+          `Source encoding: ${encoding.charset}${encoding.bom ? " (BOM)" : ""}`
+        );
+        for (const warning of warnings) {
+          console.warn(
+            `Duplicate key "${warning.path}" appears ${warning.occurrences} times; the last value is used`
+          );
+        }
+        setJsonContent(data);
+        setKeyContext(context);
+        await applyProjectConfig(filePath, detectedLangCode);
+        recentAdd(filePath, "sourceFile").catch((err) =>
+          console.warn("Failed to update recent files:", err)
+        );
+
+        // Pick up edits made to the source file in another editor
+        watchSourceFile(filePath).catch((err) =>
+          console.warn("Failed to watch source file:", err)
+        );
       } catch (parseError) {
-        setError(t("homePage.errorInvalidJson"));
+        if (!isCommandError(parseError) || parseError.code !== "InvalidJson") {
+          throw parseError;
+        }
+        const location = parseError.details as JsonErrorLocation | undefined;
+        if (location?.snippet) {
+          console.error(`${parseError.message}\n${location.snippet}`);
+        }
+        setError(
+          location
+            ? t("homePage.errorInvalidJsonAt", {
+                line: location.line,
+                column: location.column,
+              })
+            : t("homePage.errorInvalidJson")
+        );
         setJsonContent(null);
       }
     } catch (err) {
       console.error("Error selecting file:", err);
-      const errorMessage = err instanceof Error ? err.message : "Unknown error";
-      setError(errorMessage || t("homePage.errorFailedSelect"));
+      setError(getErrorMessage(err, t("homePage.errorFailedSelect")));
       setJsonContent(null);
       setSourceFilePath(null);
     } finally {
@@ -248,6 +311,52 @@ export default function HomePage() {
     }
   };
 
+  // Settings pinned by the project win over what was picked in the UI. A
+  // broken config is reported but doesn't prevent translating.
+  const applyProjectConfig = async (
+    filePath: string,
+    detectedLangCode: string | null
+  ) => {
+    let config: ProjectConfig;
+    try {
+      const loaded = await loadProjectConfig(filePath);
+      if (!loaded) {
+        return;
+      }
+      console.log(`Using project config: ${loaded.path}`);
+      config = loaded.config;
+    } catch (err) {
+      console.error("Failed to load project config:", err);
+      return;
+    }
+
+    setProjectConfig(config);
+    const sourceCode = config.sourceLocale ?? detectedLangCode;
+    if (config.sourceLocale) {
+      setSourceLanguageCode(config.sourceLocale);
+    }
+    if (config.targetLanguages.length > 0) {
+      setSelectedLanguages(
+        config.targetLanguages.filter((code) => code !== sourceCode)
+      );
+    }
+    if (config.excludedPaths.length > 0) {
+      setExcludedPaths(config.excludedPaths);
+    }
+    if (config.model) {
+      if (availableModels.some((m) => m.id === config.model)) {
+        setModel(config.model);
+      } else {
+        console.warn(
+          `Model "${config.model}" from the project config is not available`
+        );
+      }
+    }
+  };
+
+  const outputTemplate = () =>
+    projectConfig?.outputTemplate ?? getOutputTemplate();
+
   const handleTranslate = async () => {
     if (!jsonContent || selectedLanguages.length === 0) {
       setError(t("homePage.errorSelectFile"));
@@ -305,6 +414,20 @@ export default function HomePage() {
       }
     }, 100);
 
+    // Record the current state of every target, so edits made to them while
+    // the run is in progress aren't overwritten by auto-save
+    if (sourceFilePath) {
+      await Promise.all(
+        selectedLanguages.map((langCode) =>
+          resolveOutputPath(sourceFilePath, langCode, outputTemplate())
+            .then(checkFileExists)
+            .catch((err) =>
+              console.warn(`Failed to check ${langCode} target:`, err)
+            )
+        )
+      );
+    }
+
     const translator = new UnifiedTranslator(provider, apiKey, model);
     const results: TranslationResult[] = [];
     const INPUT_TOKEN_OVERHEAD = 400;
@@ -483,6 +606,7 @@ export default function HomePage() {
           jsonContent: baseJsonString,
           targetLanguage: language?.name || langCode,
           excludedPaths,
+          context: keyContext,
           model,
         });
 
@@ -540,27 +664,31 @@ export default function HomePage() {
         // Automatically save the file immediately after successful translation
         if (sourceFilePath && mergedJsonString) {
           try {
-            // Get directory and extension from source file
-            const lastSlash = sourceFilePath.lastIndexOf("/");
-            const directory =
-              lastSlash >= 0 ? sourceFilePath.substring(0, lastSlash + 1) : "";
-            const extension = sourceFilePath.substring(
-              sourceFilePath.lastIndexOf(".")
+            // Fails if the template would resolve onto the source file
+            const targetPath = await resolveOutputPath(
+              sourceFilePath,
+              langCode,
+              outputTemplate()
             );
-            const targetPath = `${directory}${langCode}${extension}`;
-
-            // Never overwrite the source file
-            if (targetPath !== sourceFilePath) {
-              console.log(`Auto-saving: ${targetPath}`);
-              await invoke("write_json_file", {
-                path: targetPath,
-                content: mergedJsonString,
-              });
-              // Use console.info with a success prefix for green color in logs
-              console.info(`[SUCCESS] Successfully saved: ${targetPath}`);
-            }
+
+            console.log(`Auto-saving: ${targetPath}`);
+            await writeLocaleFile(targetPath, mergedJsonString, {
+              sourcePath: sourceFilePath,
+              format: projectConfig?.format ?? undefined,
+              language: langCode,
+              needsReview: hasWarning,
+            });
+            // Use console.info with a success prefix for green color in logs
+            console.info(`[SUCCESS] Successfully saved: ${targetPath}`);
           } catch (saveErr) {
-            console.error(`Failed to auto-save ${langCode}:`, saveErr);
+            if (isCommandError(saveErr) && saveErr.code === "Conflict") {
+              // Someone edited the file during the run - keep their version
+              console.warn(
+                `Skipped auto-save of ${langCode}: ${saveErr.message}`
+              );
+            } else {
+              console.error(`Failed to auto-save ${langCode}:`, saveErr);
+            }
             // Don't fail the translation if save fails - user can save manually later
           }
         }
@@ -771,6 +899,72 @@ export default function HomePage() {
     };
   }, []);
 
+  // Reload the source file when it is edited outside the app
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    let unlistenFn: (() => void) | null = null;
+
+    const setupSourceListener = async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      unlistenFn = await listen<SourceChange>(
+        "source-file-changed",
+        async (event) => {
+          const { path, added, removed, changed } = event.payload;
+          console.info(
+            `Source file changed: ${added.length} new, ${changed.length} changed, ${removed.length} removed keys`
+          );
+          try {
+            const { data, context } = await loadLocaleFile(path);
+            setJsonContent(data);
+            setKeyContext(context);
+          } catch (err) {
+            console.error("Failed to reload source file:", err);
+          }
+        }
+      );
+    };
+
+    setupSourceListener();
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, []);
+
+  // Open Recent menu entries. The ref keeps the listener on the latest
+  // handler without re-subscribing on every render.
+  const selectFileRef = useRef(handleSelectFile);
+  selectFileRef.current = handleSelectFile;
+
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    let unlistenFn: (() => void) | null = null;
+
+    const setupRecentListener = async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      unlistenFn = await listen<RecentEntry>("open-recent", (event) => {
+        const { path, kind } = event.payload;
+        if (kind === "sourceFile") {
+          selectFileRef.current(path);
+        } else {
+          console.warn(`Opening project directories is not supported: ${path}`);
+        }
+      });
+    };
+
+    setupRecentListener();
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, []);
+
   // Listen for window close events from Tauri (red traffic light, menubar close)
   useEffect(() => {
     if (!isTauri()) return;
@@ -955,7 +1149,7 @@ export default function HomePage() {
           <div className="space-y-4">
             <div className="flex gap-3">
               <button
-                onClick={handleSelectFile}
+                onClick={() => handleSelectFile()}
                 disabled={isLoading || isTranslating}
                 className="flex-1 px-6 py-4 bg-primary text-button-text font-medium rounded-lg hover:bg-primary-hover focus:outline-none focus:ring-2 focus:ring-primary disabled:opacity-50 disabled:cursor-not-allowed transition-colors flex items-center justify-center gap-2"
               >
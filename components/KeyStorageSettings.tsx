@@ -0,0 +1,401 @@
+"use client";
+
+import { useState, useEffect, useCallback } from "react";
+import { Lock, Unlock, Trash2, Download, Upload } from "lucide-react";
+import { useTranslations } from "next-intl";
+import {
+  listKeys,
+  clearKeys,
+  getStorageBackend,
+  setStorageBackend,
+  getVaultStatus,
+  enableVault,
+  disableVault,
+  unlockVault,
+  lockVault,
+  setVaultAutoLock,
+  exportBundle,
+  importBundle,
+  type MergeStrategy,
+  type StorageBackendPreference,
+  type StorageBackendStatus,
+  type StoredKeyInfo,
+  type VaultStatus,
+} from "@/lib/secure-keys";
+import { getErrorMessage, isCommandError } from "@/lib/errors";
+import { isTauri } from "@/lib/utils";
+import CustomSelect from "@/components/CustomSelect";
+
+interface KeyStorageSettingsProps {
+  // Called after keys were removed or imported, so the form can reload them
+  onKeysChanged: () => void;
+}
+
+const AUTO_LOCK_OPTIONS = [0, 60, 300, 900, 3600];
+
+export default function KeyStorageSettings({
+  onKeysChanged,
+}: KeyStorageSettingsProps) {
+  const t = useTranslations("settings.keyStorage");
+
+  const [backend, setBackend] = useState<StorageBackendStatus | null>(null);
+  const [storedKeys, setStoredKeys] = useState<StoredKeyInfo[]>([]);
+  const [vault, setVault] = useState<VaultStatus | null>(null);
+  const [passphrase, setPassphrase] = useState("");
+  const [bundlePassphrase, setBundlePassphrase] = useState("");
+  const [mergeStrategy, setMergeStrategy] =
+    useState<MergeStrategy>("overwrite");
+  const [isBusy, setIsBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [notice, setNotice] = useState<string | null>(null);
+
+  const refresh = useCallback(async () => {
+    try {
+      const [backendStatus, vaultStatus, keys] = await Promise.all([
+        getStorageBackend(),
+        getVaultStatus(),
+        listKeys(),
+      ]);
+      setBackend(backendStatus);
+      setVault(vaultStatus);
+      setStoredKeys(keys);
+    } catch (err) {
+      setError(getErrorMessage(err, t("failedLoad")));
+    }
+  }, [t]);
+
+  useEffect(() => {
+    refresh();
+  }, [refresh]);
+
+  // The vault locks itself after being idle; show that without a reopen
+  useEffect(() => {
+    if (!isTauri()) return;
+
+    let unlistenFn: (() => void) | null = null;
+
+    const setupListener = async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      unlistenFn = await listen("vault-locked", () => {
+        refresh();
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlistenFn) {
+        unlistenFn();
+      }
+    };
+  }, [refresh]);
+
+  // Run a storage action, then reload everything it may have changed
+  const run = async (action: () => Promise<unknown>) => {
+    setIsBusy(true);
+    setError(null);
+    setNotice(null);
+    try {
+      await action();
+      setPassphrase("");
+    } catch (err) {
+      if (isCommandError(err) && err.code === "WrongPassphrase") {
+        setError(t("vault.wrongPassphrase"));
+      } else if (isCommandError(err) && err.code === "WeakPassphrase") {
+        setError(t("vault.weakPassphrase"));
+      } else {
+        setError(getErrorMessage(err, t("failedAction")));
+      }
+    } finally {
+      setIsBusy(false);
+      await refresh();
+    }
+  };
+
+  const handleBackendChange = (value: string) => {
+    run(() => setStorageBackend(value as StorageBackendPreference));
+  };
+
+  const handleClearKeys = () => {
+    if (!confirm(t("clearConfirm"))) return;
+    run(async () => {
+      await clearKeys();
+      onKeysChanged();
+    });
+  };
+
+  const handleExport = () => {
+    if (!bundlePassphrase) {
+      setError(t("backup.passphraseRequired"));
+      return;
+    }
+    run(async () => {
+      const exported = await exportBundle(bundlePassphrase);
+      if (exported !== null) {
+        setBundlePassphrase("");
+        setNotice(t("backup.exported", { count: exported }));
+      }
+    });
+  };
+
+  const handleImport = () => {
+    if (!bundlePassphrase) {
+      setError(t("backup.passphraseRequired"));
+      return;
+    }
+    run(async () => {
+      const summary = await importBundle(bundlePassphrase, mergeStrategy);
+      if (summary !== null) {
+        setBundlePassphrase("");
+        setNotice(t("backup.imported", summary));
+        onKeysChanged();
+      }
+    });
+  };
+
+  const handleVaultSubmit = () => {
+    if (!vault || !passphrase) return;
+    if (!vault.enabled) {
+      run(() => enableVault(passphrase));
+    } else if (!vault.unlocked) {
+      run(() => unlockVault(passphrase));
+    }
+  };
+
+  const handleDisableVault = () => {
+    if (!passphrase) {
+      setError(t("vault.passphraseRequired"));
+      return;
+    }
+    run(() => disableVault(passphrase));
+  };
+
+  if (!backend || !vault) {
+    return null;
+  }
+
+  const formatAutoLock = (seconds: number) =>
+    seconds === 0
+      ? t("vault.autoLockOff")
+      : t("vault.autoLockMinutes", { minutes: seconds / 60 });
+
+  return (
+    <div className="space-y-4 pt-4 border-t border-border">
+      {/* Backend */}
+      <div className="space-y-2">
+        <label className="block text-sm font-medium text-foreground">
+          {t("backend.title")}
+        </label>
+        <CustomSelect
+          value={backend.preference}
+          onChange={handleBackendChange}
+          options={[
+            { value: "auto", label: t("backend.auto") },
+            {
+              value: "keyring",
+              label: backend.keyringAvailable
+                ? t("backend.keyring")
+                : t("backend.keyringUnavailable"),
+            },
+            { value: "file", label: t("backend.file") },
+          ]}
+        />
+        <p className="text-xs text-foreground/60">
+          {t("backend.active", {
+            backend:
+              backend.active === "keyring"
+                ? t("backend.keyring")
+                : t("backend.file"),
+          })}
+        </p>
+      </div>
+
+      {/* Stored keys */}
+      <div className="space-y-2">
+        <h3 className="text-sm font-medium text-foreground">
+          {t("keys.title")}
+        </h3>
+        {storedKeys.length === 0 ? (
+          <p className="text-xs text-foreground/60">{t("keys.empty")}</p>
+        ) : (
+          <div className="space-y-2">
+            {storedKeys.map((info) => (
+              <div
+                key={info.key}
+                className="flex items-center gap-2 p-2 bg-background/50 rounded-lg"
+              >
+                <div className="flex-1">
+                  <div className="text-sm font-medium text-foreground font-mono">
+                    {info.key}
+                  </div>
+                  {info.updatedAt !== null && (
+                    <div className="text-xs text-foreground/60">
+                      {t("keys.updated", {
+                        date: new Date(info.updatedAt).toLocaleString(),
+                      })}
+                    </div>
+                  )}
+                </div>
+                <span className="text-xs text-foreground/60 font-mono">
+                  {info.preview ?? t("keys.hidden")}
+                </span>
+              </div>
+            ))}
+            <button
+              type="button"
+              onClick={handleClearKeys}
+              disabled={isBusy}
+              className="w-full flex items-center justify-center gap-2 px-4 py-2 text-sm font-medium text-error-text hover:bg-error-bg rounded-lg transition-colors disabled:opacity-50"
+            >
+              <Trash2 className="w-4 h-4" />
+              {t("keys.clear")}
+            </button>
+          </div>
+        )}
+      </div>
+
+      {/* Vault */}
+      <div className="space-y-2">
+        <h3 className="text-sm font-medium text-foreground">
+          {t("vault.title")}
+        </h3>
+        <p className="text-xs text-foreground/60">
+          {!vault.enabled
+            ? t("vault.disabled")
+            : vault.unlocked
+              ? t("vault.unlocked")
+              : t("vault.locked")}
+        </p>
+        {(!vault.enabled || !vault.unlocked) && (
+          <div className="flex gap-2">
+            <input
+              type="password"
+              value={passphrase}
+              onChange={(e) => setPassphrase(e.target.value)}
+              onKeyDown={(e) => {
+                // Don't submit the settings form
+                if (e.key === "Enter") {
+                  e.preventDefault();
+                  handleVaultSubmit();
+                }
+              }}
+              placeholder={t("vault.passphrasePlaceholder")}
+              className="flex-1 px-3 py-2 bg-background border border-border rounded-lg focus:outline-none focus:ring-2 focus:ring-primary text-foreground text-sm"
+            />
+            <button
+              type="button"
+              onClick={handleVaultSubmit}
+              disabled={isBusy || !passphrase}
+              className="px-4 py-2 text-sm font-medium bg-primary text-button-text rounded-lg hover:bg-primary-hover transition-colors flex items-center gap-2 disabled:opacity-50"
+            >
+              <Unlock className="w-4 h-4" />
+              {vault.enabled ? t("vault.unlock") : t("vault.enable")}
+            </button>
+          </div>
+        )}
+        {vault.enabled && vault.unlocked && (
+          <div className="space-y-2">
+            <div className="flex gap-2">
+              <CustomSelect
+                className="flex-1"
+                value={String(vault.autoLockSeconds ?? 0)}
+                onChange={(value) =>
+                  run(() => setVaultAutoLock(Number(value)))
+                }
+                options={AUTO_LOCK_OPTIONS.map((seconds) => ({
+                  value: String(seconds),
+                  label: formatAutoLock(seconds),
+                }))}
+              />
+              <button
+                type="button"
+                onClick={() => run(lockVault)}
+                disabled={isBusy}
+                className="px-4 py-2 text-sm font-medium bg-foreground/10 text-foreground rounded-lg hover:bg-foreground/20 transition-colors flex items-center gap-2 disabled:opacity-50"
+              >
+                <Lock className="w-4 h-4" />
+                {t("vault.lock")}
+              </button>
+            </div>
+            <div className="flex gap-2">
+              <input
+                type="password"
+                value={passphrase}
+                onChange={(e) => setPassphrase(e.target.value)}
+                onKeyDown={(e) => {
+                  if (e.key === "Enter") {
+                    e.preventDefault();
+                    handleDisableVault();
+                  }
+                }}
+                placeholder={t("vault.passphrasePlaceholder")}
+                className="flex-1 px-3 py-2 bg-background border border-border rounded-lg focus:outline-none focus:ring-2 focus:ring-primary text-foreground text-sm"
+              />
+              <button
+                type="button"
+                onClick={handleDisableVault}
+                disabled={isBusy}
+                className="px-4 py-2 text-sm font-medium text-error-text hover:bg-error-bg rounded-lg transition-colors disabled:opacity-50"
+              >
+                {t("vault.disable")}
+              </button>
+            </div>
+          </div>
+        )}
+      </div>
+
+      {/* Backup */}
+      <div className="space-y-2">
+        <h3 className="text-sm font-medium text-foreground">
+          {t("backup.title")}
+        </h3>
+        <p className="text-xs text-foreground/60">{t("backup.description")}</p>
+        <input
+          type="password"
+          value={bundlePassphrase}
+          onChange={(e) => setBundlePassphrase(e.target.value)}
+          onKeyDown={(e) => {
+            // Don't submit the settings form
+            if (e.key === "Enter") {
+              e.preventDefault();
+            }
+          }}
+          placeholder={t("backup.passphrasePlaceholder")}
+          className="w-full px-3 py-2 bg-background border border-border rounded-lg focus:outline-none focus:ring-2 focus:ring-primary text-foreground text-sm"
+        />
+        <CustomSelect
+          value={mergeStrategy}
+          onChange={(value) => setMergeStrategy(value as MergeStrategy)}
+          options={[
+            { value: "overwrite", label: t("backup.overwrite") },
+            { value: "keepExisting", label: t("backup.keepExisting") },
+            { value: "replace", label: t("backup.replace") },
+          ]}
+        />
+        <div className="flex gap-2">
+          <button
+            type="button"
+            onClick={handleExport}
+            disabled={isBusy}
+            className="flex-1 px-4 py-2 text-sm font-medium bg-foreground/10 text-foreground rounded-lg hover:bg-foreground/20 transition-colors flex items-center justify-center gap-2 disabled:opacity-50"
+          >
+            <Download className="w-4 h-4" />
+            {t("backup.export")}
+          </button>
+          <button
+            type="button"
+            onClick={handleImport}
+            disabled={isBusy}
+            className="flex-1 px-4 py-2 text-sm font-medium bg-foreground/10 text-foreground rounded-lg hover:bg-foreground/20 transition-colors flex items-center justify-center gap-2 disabled:opacity-50"
+          >
+            <Upload className="w-4 h-4" />
+            {t("backup.import")}
+          </button>
+        </div>
+      </div>
+
+      {notice && <p className="text-xs text-success-text">{notice}</p>}
+      {error && <p className="text-xs text-error-text">{error}</p>}
+    </div>
+  );
+}
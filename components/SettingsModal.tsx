@@ -33,6 +33,7 @@ import {
   setStoredLocale,
 } from "@/lib/i18n/locale";
 import CustomSelect from "@/components/CustomSelect";
+import KeyStorageSettings from "@/components/KeyStorageSettings";
 
 interface SettingsModalProps {
   isOpen: boolean;
@@ -260,6 +261,18 @@ export default function SettingsModal({
     setApiKeys((prev) => ({ ...prev, [provider]: value }));
   };
 
+  // Reload the form after keys were cleared or imported in storage settings
+  const handleKeysChanged = async () => {
+    const storedKeys: Record<Provider, string> = {
+      openai: (await getKey("openai-api-key")) || "",
+      anthropic: (await getKey("anthropic-api-key")) || "",
+      mistral: (await getKey("mistral-api-key")) || "",
+      openrouter: (await getKey("openrouter-api-key")) || "",
+    };
+    setApiKeys(storedKeys);
+    onSave(storedKeys);
+  };
+
   const handleOpenAPIKey = async (url: string) => {
     try {
       await open(url);
@@ -584,6 +597,9 @@ export default function SettingsModal({
               <div className="flex items-start gap-2 text-xs text-foreground/60 pt-2">
                 <p className="flex-1">{t("apiKeys.secureStorage")}</p>
               </div>
+              {isTauriApp && (
+                <KeyStorageSettings onKeysChanged={handleKeysChanged} />
+              )}
             </div>
           )}
 
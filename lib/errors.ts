@@ -0,0 +1,52 @@
+/**
+ * Errors returned by Tauri commands.
+ *
+ * Every command rejects with `{ code, message, details? }`. Branch on `code`,
+ * which is stable; `message` is an English fallback.
+ */
+export type CommandErrorCode =
+  | "NotFound"
+  | "AlreadyExists"
+  | "Conflict"
+  | "PermissionDenied"
+  | "InvalidJson"
+  | "InvalidInput"
+  | "InvalidKey"
+  | "PathOutsideScope"
+  | "VaultLocked"
+  | "WrongPassphrase"
+  | "WeakPassphrase"
+  | "Unavailable"
+  | "Io"
+  | "Internal";
+
+export interface CommandError {
+  code: CommandErrorCode;
+  message: string;
+  details?: Record<string, unknown>;
+}
+
+/**
+ * Check whether a caught value is a command error
+ */
+export function isCommandError(error: unknown): error is CommandError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    "code" in error &&
+    "message" in error
+  );
+}
+
+/**
+ * Get a displayable message from anything a command or fetch may throw
+ */
+export function getErrorMessage(error: unknown, fallback: string): string {
+  if (isCommandError(error) || error instanceof Error) {
+    return error.message || fallback;
+  }
+  if (typeof error === "string") {
+    return error || fallback;
+  }
+  return fallback;
+}
@@ -307,7 +307,8 @@ export class UnifiedTranslator implements Translator {
     const systemPrompt = this.buildTranslationPrompt(
       input.targetLanguage,
       input.excludedPaths,
-      true
+      true,
+      input.context
     );
     const systemPromptSize = new Blob([systemPrompt]).size;
     console.log(
@@ -462,7 +463,8 @@ export class UnifiedTranslator implements Translator {
     const systemPrompt = this.buildTranslationPrompt(
       input.targetLanguage,
       input.excludedPaths,
-      true
+      true,
+      input.context
     );
 
     // Translate each chunk with retry logic
@@ -645,7 +647,8 @@ export class UnifiedTranslator implements Translator {
   private buildTranslationPrompt(
     targetLanguage: string,
     excludedPaths: string[],
-    useToon: boolean = false
+    useToon: boolean = false,
+    context: Record<string, string> = {}
   ): string {
     const formatName = useToon ? "TOON" : "JSON";
     let prompt = `Translate to ${targetLanguage}. Rules:
@@ -671,6 +674,12 @@ export class UnifiedTranslator implements Translator {
       prompt += `\nDo not translate these paths:\n${excludedPaths.map((path) => `- ${path}`).join("\n")}`;
     }
 
+    // Descriptions help pick the right wording; they are not part of the output
+    const notes = Object.entries(context);
+    if (notes.length > 0) {
+      prompt += `\nContext for some keys (use it to choose the wording; do not translate or output it):\n${notes.map(([path, note]) => `- ${path}: ${note}`).join("\n")}`;
+    }
+
     return prompt;
   }
 
@@ -0,0 +1,368 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { CommandError } from "./errors";
+import { getAllLanguages } from "./languages";
+
+/**
+ * Wrappers for the locale file commands.
+ *
+ * Paths must be inside a project granted through the source file dialog.
+ */
+
+export type Charset =
+  | "utf-8"
+  | "utf-16le"
+  | "utf-16be"
+  | "utf-32le"
+  | "utf-32be";
+
+export interface Encoding {
+  charset: Charset;
+  bom: boolean;
+}
+
+/**
+ * A file decoded by `read_json_file`. BOMs are stripped from `content`.
+ */
+export interface DecodedText {
+  content: string;
+  encoding: Encoding;
+}
+
+export interface DuplicateKeyWarning {
+  kind: "duplicateKey";
+  /** Dotted key path, e.g. `home.title` */
+  path: string;
+  occurrences: number;
+}
+
+export type LoadWarning = DuplicateKeyWarning;
+
+export interface LoadedLocaleFile {
+  data: unknown;
+  encoding: Encoding;
+  warnings: LoadWarning[];
+  /** Notes for the translator by key path, e.g. ARB descriptions */
+  context: Record<string, string>;
+}
+
+/**
+ * Location of a JSON syntax error, from the details of an `InvalidJson` error
+ */
+export interface JsonErrorLocation {
+  line: number;
+  column: number;
+  snippet?: string;
+}
+
+/**
+ * Read and parse a locale file: JSON, a gettext `.po`/`.pot` catalog, XLIFF,
+ * Android `strings.xml`, Apple `.strings`/`.stringsdict`/`.xcstrings`, or a
+ * Flutter `.arb` bundle.
+ * Rejects with an `InvalidJson` command error whose details are a
+ * `JsonErrorLocation` if a JSON file doesn't parse.
+ */
+export async function loadLocaleFile(path: string): Promise<LoadedLocaleFile> {
+  return invoke<LoadedLocaleFile>("load_locale_file", { path });
+}
+
+/**
+ * Formatting rules that win over the style detected from the source file
+ */
+export interface FormatOverrides {
+  /** Number of spaces, "tab" or "compact" (everything on one line) */
+  indent?: number | "tab" | "compact";
+  lineEnding?: "lf" | "crlf";
+  trailingNewline?: boolean;
+  /** Write non-ASCII characters as \uXXXX escapes */
+  escapeUnicode?: boolean;
+}
+
+export interface WriteOptions {
+  /** File whose formatting and encoding the output should match */
+  sourcePath?: string;
+  /** Number of backups to keep; 0 disables backups */
+  keepBackups?: number;
+  /** Write even if the file changed on disk since it was read or checked */
+  overwrite?: boolean;
+  format?: FormatOverrides;
+  /** Language code of the file, for formats that record it (e.g. PO headers) */
+  language?: string;
+  /** Mark the translations for review, in formats that can (e.g. XLIFF state) */
+  needsReview?: boolean;
+}
+
+/**
+ * Details of a `Conflict` error from `write_json_file`
+ */
+export interface WriteConflict {
+  path: string;
+  /** Current contents on disk, or null if the file was deleted */
+  onDisk: string | null;
+  /** Contents that were about to be written */
+  pending: string;
+}
+
+const OUTPUT_TEMPLATE_KEY = "localekit-output-template";
+
+/**
+ * Output path template for translated files, e.g. `{dir}/{language}/{basename}`.
+ * Undefined means the default, `{dir}/{lang}{ext}`.
+ */
+export function getOutputTemplate(): string | undefined {
+  if (typeof window === "undefined") {
+    return undefined;
+  }
+  return localStorage.getItem(OUTPUT_TEMPLATE_KEY) || undefined;
+}
+
+export function setOutputTemplate(template: string | null): void {
+  if (template) {
+    localStorage.setItem(OUTPUT_TEMPLATE_KEY, template);
+  } else {
+    localStorage.removeItem(OUTPUT_TEMPLATE_KEY);
+  }
+}
+
+/**
+ * Path of the translated file for `langCode`. Rejects if the template is
+ * invalid, resolves onto the source file or leaves the granted project.
+ * String catalogs (`.xcstrings`) hold every language, so for those this is
+ * the source file itself.
+ */
+export async function resolveOutputPath(
+  sourcePath: string,
+  langCode: string,
+  template?: string
+): Promise<string> {
+  return invoke<string>("resolve_output_path", {
+    template,
+    sourcePath,
+    language: langCode,
+  });
+}
+
+export interface OutputPreview {
+  language: string;
+  path: string | null;
+  exists: boolean;
+  /** Whether the path is inside a granted project and can be written */
+  inScope: boolean;
+  error: CommandError | null;
+}
+
+/**
+ * Resolve an output template for several languages without writing anything
+ */
+export async function previewOutputPaths(
+  sourcePath: string,
+  languages: string[],
+  template?: string
+): Promise<OutputPreview[]> {
+  return invoke<OutputPreview[]>("preview_output_paths", {
+    template,
+    sourcePath,
+    languages,
+  });
+}
+
+/**
+ * Settings pinned by a `localekit.toml` or `.localekit.json` next to the
+ * source file. Unset keys are null, or empty for lists.
+ */
+export interface ProjectConfig {
+  sourceLocale: string | null;
+  targetLanguages: string[];
+  excludedPaths: string[];
+  model: string | null;
+  outputTemplate: string | null;
+  /** Absolute path */
+  glossary: string | null;
+  format: FormatOverrides | null;
+}
+
+export interface LoadedProjectConfig {
+  /** The config file that was read */
+  path: string;
+  config: ProjectConfig;
+}
+
+/**
+ * Load the project config for a source file. Resolves to null if there is
+ * none; rejects with an `InvalidInput` or `InvalidJson` error (details:
+ * `{ path, line, column }`) if it doesn't parse.
+ */
+export async function loadProjectConfig(
+  sourcePath: string
+): Promise<LoadedProjectConfig | null> {
+  return invoke<LoadedProjectConfig | null>("load_project_config", {
+    sourcePath,
+  });
+}
+
+/**
+ * Check whether a file exists. This also records its current contents, so a
+ * later write fails with a `Conflict` error if someone changes it meanwhile.
+ */
+export async function checkFileExists(path: string): Promise<boolean> {
+  return invoke<boolean>("check_file_exists", { path });
+}
+
+/**
+ * Write a locale file. Rejects with a `Conflict` error (details:
+ * `WriteConflict`) if the file changed since it was read or checked.
+ */
+export async function writeLocaleFile(
+  path: string,
+  content: string,
+  options?: WriteOptions
+): Promise<void> {
+  await invoke("write_json_file", { path, content, options });
+}
+
+/**
+ * Payload of the `source-file-changed` event. Keys are dotted paths.
+ */
+export interface SourceChange {
+  path: string;
+  added: string[];
+  removed: string[];
+  changed: string[];
+}
+
+/**
+ * Emit `source-file-changed` whenever the file's keys change on disk.
+ * Replaces any previous watch.
+ */
+export async function watchSourceFile(path: string): Promise<void> {
+  await invoke("watch_source_file", { path });
+}
+
+export async function unwatchSourceFile(): Promise<void> {
+  await invoke("unwatch_source_file");
+}
+
+export interface BackupInfo {
+  path: string;
+  createdAt: number;
+  size: number;
+}
+
+/**
+ * List backups of a locale file, newest first
+ */
+export async function listBackups(path: string): Promise<BackupInfo[]> {
+  return invoke<BackupInfo[]>("list_backups", { path });
+}
+
+/**
+ * Replace a locale file with one of its backups. The current contents are
+ * backed up first.
+ */
+export async function restoreBackup(
+  path: string,
+  backup: string,
+  keepBackups?: number
+): Promise<void> {
+  await invoke("restore_backup", { path, backup, keepBackups });
+}
+
+export type ProjectLayout = "filePerLocale" | "folderPerLocale";
+
+export interface ProjectLocaleFile {
+  path: string;
+  /** File stem in folder-per-locale layouts, or the prefix in `messages.de.json` */
+  namespace: string | null;
+}
+
+export interface LocaleGroup {
+  layout: ProjectLayout;
+  /** Directory holding the locale files or locale folders */
+  base: string;
+  sourceLanguage: string;
+  source: ProjectLocaleFile[];
+  targets: Record<string, ProjectLocaleFile[]>;
+}
+
+export interface ProjectMap {
+  root: string;
+  groups: LocaleGroup[];
+  /** JSON files whose language couldn't be inferred */
+  unrecognized: string[];
+}
+
+/**
+ * Let the user pick a project directory and grant access to it
+ */
+export async function selectProjectDirectory(): Promise<string | null> {
+  return invoke<string | null>("select_project_directory");
+}
+
+/**
+ * Find the locale files in a project directory, inferring each file's
+ * language from the configured language list
+ */
+export async function scanLocaleDirectory(
+  dir: string,
+  sourceLanguage?: string
+): Promise<ProjectMap> {
+  const languages = getAllLanguages().map((language) => language.code);
+  return invoke<ProjectMap>("scan_locale_directory", {
+    dir,
+    languages,
+    sourceLanguage,
+  });
+}
+
+export interface NamespaceProject {
+  base: string;
+  sourceLanguage: string;
+  /** Namespace name → file in the source language's folder */
+  namespaces: Record<string, string>;
+  /** Language code → existing folder, including the source language */
+  languages: Record<string, string>;
+}
+
+export interface LoadedNamespaces {
+  project: NamespaceProject;
+  /** Namespace name → parsed source file */
+  files: Record<string, LoadedLocaleFile>;
+}
+
+/**
+ * Load every namespace of the source language in a folder-per-locale
+ * project (`locales/en/common.json`, `locales/en/auth.json`, ...)
+ */
+export async function loadProjectNamespaces(
+  base: string,
+  sourceLanguage: string
+): Promise<LoadedNamespaces> {
+  const languages = getAllLanguages().map((language) => language.code);
+  return invoke<LoadedNamespaces>("load_project_namespaces", {
+    base,
+    languages,
+    sourceLanguage,
+  });
+}
+
+/**
+ * Write a translated namespace into its language folder, creating the folder
+ * if needed. Resolves to the path written.
+ */
+export async function writeProjectNamespace(
+  target: {
+    base: string;
+    sourceLanguage: string;
+    language: string;
+    namespace: string;
+  },
+  content: string,
+  options?: WriteOptions
+): Promise<string> {
+  const languages = getAllLanguages().map((language) => language.code);
+  return invoke<string>("write_project_namespace", {
+    target,
+    languages,
+    content,
+    options,
+  });
+}
@@ -0,0 +1,38 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/**
+ * Wrappers for the recently opened files list. The list is kept by the
+ * backend and mirrored in the native "Open Recent" menu, which emits
+ * `open-recent` with a `RecentEntry` when an entry is picked.
+ */
+
+export type RecentKind = "sourceFile" | "projectDirectory";
+
+export interface RecentEntry {
+  path: string;
+  kind: RecentKind;
+  /** Milliseconds since the Unix epoch */
+  openedAt: number;
+}
+
+/**
+ * Recently opened files and projects, newest first
+ */
+export async function recentList(): Promise<RecentEntry[]> {
+  return invoke<RecentEntry[]>("recent_list");
+}
+
+/**
+ * Move a path to the top of the list. The path must have been granted
+ * through a dialog. Resolves to the updated list.
+ */
+export async function recentAdd(
+  path: string,
+  kind: RecentKind
+): Promise<RecentEntry[]> {
+  return invoke<RecentEntry[]>("recent_add", { path, kind });
+}
+
+export async function recentClear(): Promise<void> {
+  await invoke("recent_clear");
+}
@@ -1,15 +1,31 @@
 import { invoke } from "@tauri-apps/api/core";
 import { isTauri } from "./utils";
+import { isCommandError } from "./errors";
+import {
+  getCustomLanguages,
+  saveCustomLanguages,
+  type Language,
+} from "./languages";
 
 /**
  * Secure storage wrapper for API keys using OS-level secure storage
  * - macOS: Keychain
- * - Windows: DPAPI
- * - Linux: System keyring
+ * - Windows: Credential Manager
+ * - Linux: Secret Service (GNOME Keyring, KWallet)
  *
+ * When no keyring is available, keys are kept in encrypted files instead.
  * In web mode (non-Tauri), falls back to localStorage
  */
 
+export type StorageBackend = "file" | "keyring";
+export type StorageBackendPreference = "auto" | StorageBackend;
+
+export interface StorageBackendStatus {
+  preference: StorageBackendPreference;
+  active: StorageBackend;
+  keyringAvailable: boolean;
+}
+
 const KEY_PREFIX = "localekit_";
 
 /**
@@ -29,6 +45,9 @@ export async function getKey(key: string): Promise<string | null> {
     return value || null;
   } catch (error) {
     // Key might not exist yet, which is not an error
+    if (!isCommandError(error) || error.code !== "NotFound") {
+      console.error(`Failed to read ${key} from secure storage:`, error);
+    }
     return null;
   }
 }
@@ -88,6 +107,185 @@ export async function hasKey(key: string): Promise<boolean> {
   }
 }
 
+export interface StoredKeyInfo {
+  key: string;
+  backend: StorageBackend;
+  createdAt: number | null;
+  updatedAt: number | null;
+  preview: string | null;
+}
+
+/**
+ * List stored keys with timestamps and a masked preview of each value.
+ * Key names are returned without the app prefix.
+ */
+export async function listKeys(): Promise<StoredKeyInfo[]> {
+  if (!isTauri()) {
+    return [];
+  }
+
+  const keys = await invoke<StoredKeyInfo[]>("secure_storage_list");
+  return keys
+    .filter((info) => info.key.startsWith(KEY_PREFIX))
+    .map((info) => ({ ...info, key: info.key.slice(KEY_PREFIX.length) }));
+}
+
+/**
+ * Remove every key from secure storage. Returns the number of keys removed.
+ */
+export async function clearKeys(): Promise<number> {
+  if (!isTauri()) {
+    return 0;
+  }
+
+  return invoke<number>("secure_storage_clear");
+}
+
+/**
+ * Get the active secure storage backend and whether the OS keyring is usable
+ */
+export async function getStorageBackend(): Promise<StorageBackendStatus | null> {
+  if (!isTauri()) {
+    return null;
+  }
+
+  return invoke<StorageBackendStatus>("secure_storage_backend");
+}
+
+/**
+ * Switch the secure storage backend, moving existing keys to the new one
+ */
+export async function setStorageBackend(
+  preference: StorageBackendPreference
+): Promise<StorageBackendStatus> {
+  return invoke<StorageBackendStatus>("secure_storage_set_backend", {
+    preference,
+  });
+}
+
+export type MergeStrategy = "overwrite" | "keepExisting" | "replace";
+
+interface BundlePreferences {
+  customLanguages?: Language[];
+  selectedModel?: string | null;
+}
+
+/**
+ * Export all keys, custom languages and settings into one encrypted bundle.
+ * The destination is picked with a native save dialog. Returns the number of
+ * keys exported, or null if the dialog was cancelled.
+ */
+export async function exportBundle(passphrase: string): Promise<number | null> {
+  const preferences: BundlePreferences = {
+    customLanguages: getCustomLanguages(),
+    selectedModel: localStorage.getItem("selected-model"),
+  };
+
+  const summary = await invoke<{ exported: number } | null>(
+    "secure_storage_export",
+    { passphrase, preferences }
+  );
+  return summary ? summary.exported : null;
+}
+
+/**
+ * Import a bundle created by `exportBundle` and restore its settings. The
+ * bundle is picked with a native open dialog. Returns how many keys were
+ * imported and skipped, or null if the dialog was cancelled.
+ */
+export async function importBundle(
+  passphrase: string,
+  mergeStrategy: MergeStrategy
+): Promise<{ imported: number; skipped: number } | null> {
+  const summary = await invoke<{
+    imported: number;
+    skipped: number;
+    preferences: BundlePreferences | null;
+  } | null>("secure_storage_import", { passphrase, mergeStrategy });
+  if (!summary) {
+    return null;
+  }
+
+  const preferences = summary.preferences;
+  if (preferences?.customLanguages) {
+    const existing = getCustomLanguages();
+    const incoming = preferences.customLanguages.filter(
+      (lang) =>
+        mergeStrategy !== "keepExisting" ||
+        !existing.some((current) => current.code === lang.code)
+    );
+    const kept =
+      mergeStrategy === "replace"
+        ? []
+        : existing.filter(
+            (current) => !incoming.some((lang) => lang.code === current.code)
+          );
+    saveCustomLanguages([...kept, ...incoming]);
+  }
+  if (preferences?.selectedModel && mergeStrategy !== "keepExisting") {
+    localStorage.setItem("selected-model", preferences.selectedModel);
+  }
+
+  return { imported: summary.imported, skipped: summary.skipped };
+}
+
+export interface VaultStatus {
+  enabled: boolean;
+  unlocked: boolean;
+  autoLockSeconds: number | null;
+}
+
+/**
+ * Get whether the passphrase vault is enabled and currently unlocked
+ */
+export async function getVaultStatus(): Promise<VaultStatus | null> {
+  if (!isTauri()) {
+    return null;
+  }
+
+  return invoke<VaultStatus>("vault_status");
+}
+
+/**
+ * Seal all stored keys under a master passphrase
+ */
+export async function enableVault(
+  passphrase: string,
+  autoLockSeconds?: number
+): Promise<VaultStatus> {
+  return invoke<VaultStatus>("vault_enable", { passphrase, autoLockSeconds });
+}
+
+/**
+ * Remove the master passphrase and store keys without it again
+ */
+export async function disableVault(passphrase: string): Promise<VaultStatus> {
+  return invoke<VaultStatus>("vault_disable", { passphrase });
+}
+
+/**
+ * Unlock the vault so keys can be read and written
+ */
+export async function unlockVault(passphrase: string): Promise<VaultStatus> {
+  return invoke<VaultStatus>("vault_unlock", { passphrase });
+}
+
+/**
+ * Lock the vault immediately (emits "vault-locked")
+ */
+export async function lockVault(): Promise<VaultStatus> {
+  return invoke<VaultStatus>("vault_lock");
+}
+
+/**
+ * Change the idle time after which the vault locks itself (0 disables it)
+ */
+export async function setVaultAutoLock(
+  autoLockSeconds: number
+): Promise<VaultStatus> {
+  return invoke<VaultStatus>("vault_set_auto_lock", { autoLockSeconds });
+}
+
 /**
  * Migrate API keys from localStorage to secure storage
  * This is a one-time migration that runs on app startup
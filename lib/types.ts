@@ -4,6 +4,8 @@ export interface TranslationInput {
   jsonContent: string;
   targetLanguage: string;
   excludedPaths: string[];
+  /** Notes for the translator by key path, e.g. ARB descriptions */
+  context?: Record<string, string>;
   model?: string;
   temperature?: number;
 }
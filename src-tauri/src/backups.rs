@@ -0,0 +1,318 @@
+// Rotated backups of locale files overwritten by write_json_file
+//
+// Before an existing file is replaced, its current contents are copied to
+// `.localekit/backups/` in the same directory, named after the file plus a
+// local timestamp, e.g. `.localekit/backups/de_de.2026-10-15T10-00-00-123.json`.
+// The timestamp sorts lexicographically, so file names alone give the order,
+// and only the newest `keep` backups of each file are kept.
+//
+// Nothing is backed up when a write wouldn't change the file, or when the
+// newest backup already holds the current contents: auto-save rewrites the
+// same file over and over, and would otherwise push every real earlier
+// version out of the rotation.
+use chrono::Local;
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::CommandError;
+use crate::fs_util::{write_atomic, FileMode};
+
+pub const DEFAULT_KEEP: usize = 10;
+
+const BACKUP_DIR: &str = ".localekit/backups";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H-%M-%S-%3f";
+// Length of a timestamp produced by TIMESTAMP_FORMAT
+const TIMESTAMP_LEN: usize = 23;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    path: PathBuf,
+    // Milliseconds since the Unix epoch
+    created_at: u64,
+    size: u64,
+}
+
+// How backups of one file are named
+struct BackupName {
+    dir: PathBuf,
+    stem: String,
+    extension: Option<String>,
+}
+
+impl BackupName {
+    fn for_file(path: &Path) -> Result<Self, CommandError> {
+        let parent = path
+            .parent()
+            .ok_or_else(|| CommandError::invalid_input("File has no parent directory"))?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| CommandError::invalid_input("File has no name"))?;
+
+        Ok(Self {
+            dir: parent.join(BACKUP_DIR),
+            stem: stem.to_string(),
+            extension: path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(str::to_string),
+        })
+    }
+
+    fn file_name(&self, timestamp: &str) -> String {
+        match &self.extension {
+            Some(extension) => format!("{}.{}.{}", self.stem, timestamp, extension),
+            None => format!("{}.{}", self.stem, timestamp),
+        }
+    }
+
+    // Whether `name` is a backup of this file
+    fn matches(&self, name: &str) -> bool {
+        let rest = match name
+            .strip_prefix(&self.stem)
+            .and_then(|rest| rest.strip_prefix('.'))
+        {
+            Some(rest) => rest,
+            None => return false,
+        };
+        let timestamp = match &self.extension {
+            Some(extension) => match rest.strip_suffix(extension.as_str()) {
+                Some(rest) => match rest.strip_suffix('.') {
+                    Some(timestamp) => timestamp,
+                    None => return false,
+                },
+                None => return false,
+            },
+            None => rest,
+        };
+
+        timestamp.len() == TIMESTAMP_LEN
+            && timestamp
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '-' || c == 'T')
+    }
+}
+
+// Copy the current contents of `path` into its backup directory before it is
+// replaced with `new_contents`, if the file exists and backups are enabled
+// (`keep > 0`), then drop old backups. Returns the backup written, if any.
+pub fn backup_before_write(
+    path: &Path,
+    new_contents: &[u8],
+    keep: usize,
+) -> Result<Option<PathBuf>, CommandError> {
+    if keep == 0 {
+        return Ok(None);
+    }
+
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(CommandError::io("Failed to read file for backup", e)),
+    };
+    if contents == new_contents {
+        return Ok(None);
+    }
+
+    let name = BackupName::for_file(path)?;
+    if let Some(newest) = backup_paths(&name)?.first() {
+        let newest = fs::read(newest).map_err(|e| CommandError::io("Failed to read backup", e))?;
+        if newest == contents {
+            return Ok(None);
+        }
+    }
+
+    fs::create_dir_all(&name.dir)
+        .map_err(|e| CommandError::io("Failed to create backup directory", e))?;
+
+    let timestamp = Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let backup_path = name.dir.join(name.file_name(&timestamp));
+    write_atomic(&backup_path, &contents, FileMode::Default)
+        .map_err(|e| CommandError::io("Failed to write backup", e))?;
+
+    prune(&name, keep)?;
+    Ok(Some(backup_path))
+}
+
+// Backups of `path`, newest first
+pub fn list(path: &Path) -> Result<Vec<BackupInfo>, CommandError> {
+    let name = BackupName::for_file(path)?;
+    let mut backups = Vec::new();
+
+    for entry_path in backup_paths(&name)? {
+        let metadata =
+            fs::metadata(&entry_path).map_err(|e| CommandError::io("Failed to read backup", e))?;
+        let created_at = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+
+        backups.push(BackupInfo {
+            path: entry_path,
+            created_at,
+            size: metadata.len(),
+        });
+    }
+
+    Ok(backups)
+}
+
+// Replace `path` with one of its backups. The current contents are backed up
+// first, so a restore can itself be undone.
+pub fn restore(path: &Path, backup: &Path, keep: usize) -> Result<(), CommandError> {
+    let name = BackupName::for_file(path)?;
+    let is_backup_of_path = backup.parent() == Some(name.dir.as_path())
+        && backup
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(|file_name| name.matches(file_name));
+    if !is_backup_of_path {
+        return Err(CommandError::invalid_input(format!(
+            "'{}' is not a backup of '{}'",
+            backup.display(),
+            path.display()
+        )));
+    }
+
+    let contents = fs::read(backup).map_err(|e| CommandError::io("Failed to read backup", e))?;
+
+    backup_before_write(path, &contents, keep)?;
+    write_atomic(path, &contents, FileMode::Default)
+        .map_err(|e| CommandError::io("Failed to restore backup", e))
+}
+
+// Remove all but the newest `keep` backups
+fn prune(name: &BackupName, keep: usize) -> Result<(), CommandError> {
+    for path in backup_paths(name)?.into_iter().skip(keep) {
+        fs::remove_file(&path).map_err(|e| CommandError::io("Failed to remove old backup", e))?;
+    }
+
+    Ok(())
+}
+
+// Paths of existing backups, newest first
+fn backup_paths(name: &BackupName) -> Result<Vec<PathBuf>, CommandError> {
+    let entries = match fs::read_dir(&name.dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(CommandError::io("Failed to read backup directory", e)),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| CommandError::io("Failed to read backup directory", e))?;
+        let is_backup = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|file_name| name.matches(file_name));
+        if is_backup {
+            paths.push(entry.path());
+        }
+    }
+
+    paths.sort();
+    paths.reverse();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use std::thread;
+    use std::time::Duration;
+
+    // Write `contents` the way write_json_file does, backing up first. Waits
+    // a little so backup timestamps differ.
+    fn save(path: &Path, contents: &str, keep: usize) -> Option<PathBuf> {
+        thread::sleep(Duration::from_millis(2));
+        let backup = backup_before_write(path, contents.as_bytes(), keep).unwrap();
+        fs::write(path, contents).unwrap();
+        backup
+    }
+
+    fn backed_up_contents(path: &Path) -> Vec<String> {
+        list(path)
+            .unwrap()
+            .iter()
+            .map(|backup| fs::read_to_string(&backup.path).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn old_backups_are_pruned_newest_first() {
+        let dir = test_dir("backups-prune");
+        let path = dir.join("de_de.json");
+        assert_eq!(save(&path, "v0", 3), None);
+        for version in 1..=5 {
+            save(&path, &format!("v{}", version), 3).unwrap();
+        }
+
+        assert_eq!(backed_up_contents(&path), ["v4", "v3", "v2"]);
+        let backups = list(&path).unwrap();
+        assert!(backups[0].path.starts_with(dir.join(BACKUP_DIR)));
+        assert_eq!(backups[0].size, 2);
+
+        // Other files' backups in the same directory are left alone
+        let other = dir.join("de_de.po");
+        save(&other, "a", 3);
+        save(&other, "b", 3).unwrap();
+        assert_eq!(backed_up_contents(&path), ["v4", "v3", "v2"]);
+        assert_eq!(backed_up_contents(&other), ["a"]);
+    }
+
+    #[test]
+    fn unchanged_writes_keep_earlier_versions() {
+        let dir = test_dir("backups-unchanged");
+        let path = dir.join("de_de.json");
+        save(&path, "first", 2);
+        save(&path, "second", 2).unwrap();
+
+        // Auto-save writing the same content again
+        for _ in 0..5 {
+            assert_eq!(save(&path, "second", 2), None);
+        }
+        assert_eq!(backed_up_contents(&path), ["first"]);
+
+        // A backup of content that is already the newest backup is skipped
+        fs::write(&path, "first").unwrap();
+        assert_eq!(save(&path, "third", 2), None);
+        assert_eq!(backed_up_contents(&path), ["first"]);
+    }
+
+    #[test]
+    fn disabled_backups_write_nothing() {
+        let dir = test_dir("backups-disabled");
+        let path = dir.join("de_de.json");
+        save(&path, "first", 0);
+        assert_eq!(save(&path, "second", 0), None);
+        assert!(!dir.join(BACKUP_DIR).exists());
+    }
+
+    #[test]
+    fn restore_round_trips_and_can_be_undone() {
+        let dir = test_dir("backups-restore");
+        let path = dir.join("de_de.json");
+        save(&path, "good", 5);
+        let backup = save(&path, "broken", 5).unwrap();
+
+        thread::sleep(Duration::from_millis(2));
+        restore(&path, &backup, 5).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "good");
+        // The overwritten version is the newest backup now
+        assert_eq!(backed_up_contents(&path), ["broken", "good"]);
+
+        let foreign = dir.join("fr_fr.json");
+        fs::write(&foreign, "fr").unwrap();
+        let Err(error) = restore(&path, &foreign, 5) else {
+            panic!("a file that isn't a backup was restored");
+        };
+        assert!(error.message.contains("is not a backup"));
+    }
+}
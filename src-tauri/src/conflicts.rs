@@ -0,0 +1,161 @@
+// Detects locale files that changed on disk behind LocaleKit's back
+//
+// A translation run can take minutes, and someone may edit `fr_fr.json` in the
+// meantime. Whenever a file is read or checked, its fingerprint (the SHA-256
+// of its contents, or the fact that it didn't exist) is remembered.
+// Hashing rather than comparing modification times means a `touch` or a save
+// without changes isn't treated as a conflict. write_json_file compares the
+// file against that fingerprint and fails with a `Conflict` error carrying
+// both versions if it changed, so the UI can offer to overwrite, merge or
+// skip. After a successful write the new contents become the baseline.
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::encoding;
+use crate::error::{CommandError, ErrorCode};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Fingerprint {
+    Missing,
+    Present([u8; 32]),
+}
+
+impl Fingerprint {
+    fn of(path: &Path) -> Result<Self, CommandError> {
+        match fs::read(path) {
+            Ok(contents) => Ok(Self::Present(Sha256::digest(&contents).into())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::Missing),
+            Err(e) => Err(CommandError::io("Failed to read file", e)),
+        }
+    }
+}
+
+// Managed state holding the baseline fingerprint of every tracked file
+#[derive(Default)]
+pub struct FileTracker {
+    baselines: Mutex<HashMap<PathBuf, Fingerprint>>,
+}
+
+impl FileTracker {
+    // Make the current state of `path` the baseline, after it was read,
+    // checked or written
+    pub fn record(&self, path: &Path) -> Result<(), CommandError> {
+        let fingerprint = Fingerprint::of(path)?;
+        self.lock()?.insert(path.to_path_buf(), fingerprint);
+        Ok(())
+    }
+
+    // Fail with `Conflict` if `path` changed since its baseline. Untracked
+    // files have nothing to conflict with. `pending` is the content about to
+    // be written, returned to the frontend alongside the version on disk.
+    pub fn check_unchanged(&self, path: &Path, pending: &str) -> Result<(), CommandError> {
+        let baselines = self.lock()?;
+        let baseline = match baselines.get(path) {
+            Some(baseline) => baseline,
+            None => return Ok(()),
+        };
+
+        let current = Fingerprint::of(path)?;
+        if current == *baseline {
+            return Ok(());
+        }
+
+        let on_disk = match current {
+            Fingerprint::Missing => None,
+            Fingerprint::Present(_) => Some(
+                encoding::read_text(path)
+                    .map(|text| text.content)
+                    .or_else(|_| {
+                        fs::read(path)
+                            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                            .map_err(|e| CommandError::io("Failed to read file", e))
+                    })?,
+            ),
+        };
+
+        Err(CommandError::new(
+            ErrorCode::Conflict,
+            format!("'{}' changed on disk since it was read", path.display()),
+        )
+        .with_details(json!({
+            "path": path,
+            "onDisk": on_disk,
+            "pending": pending,
+        })))
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<PathBuf, Fingerprint>>, CommandError> {
+        self.baselines
+            .lock()
+            .map_err(|_| CommandError::internal("File tracker is poisoned"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use serde_json::Value;
+
+    #[test]
+    fn external_edits_after_a_read_are_refused() {
+        let dir = test_dir("conflicts-edit");
+        let path = dir.join("fr_fr.json");
+        fs::write(&path, "{\"a\": \"un\"}").unwrap();
+        let tracker = FileTracker::default();
+        tracker.record(&path).unwrap();
+
+        fs::write(&path, "{\"a\": \"edited\"}").unwrap();
+        let error = tracker
+            .check_unchanged(&path, "{\"a\": \"new\"}")
+            .unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::Conflict);
+        let details = error.details.unwrap();
+        assert_eq!(details["onDisk"], "{\"a\": \"edited\"}");
+        assert_eq!(details["pending"], "{\"a\": \"new\"}");
+    }
+
+    #[test]
+    fn files_recorded_after_their_own_write_pass() {
+        let dir = test_dir("conflicts-own-write");
+        let path = dir.join("fr_fr.json");
+        let tracker = FileTracker::default();
+
+        // Untracked files have nothing to conflict with
+        assert!(tracker.check_unchanged(&path, "{}").is_ok());
+
+        // A file that didn't exist when checked, then written by the app
+        tracker.record(&path).unwrap();
+        assert!(tracker.check_unchanged(&path, "{}").is_ok());
+        fs::write(&path, "{}").unwrap();
+        tracker.record(&path).unwrap();
+        assert!(tracker.check_unchanged(&path, "{\"a\": 1}").is_ok());
+
+        // Rewriting the same contents isn't a change
+        fs::write(&path, "{}").unwrap();
+        assert!(tracker.check_unchanged(&path, "{\"a\": 1}").is_ok());
+    }
+
+    #[test]
+    fn files_created_or_deleted_behind_the_app_conflict() {
+        let dir = test_dir("conflicts-created");
+        let path = dir.join("fr_fr.json");
+        let tracker = FileTracker::default();
+        tracker.record(&path).unwrap();
+
+        fs::write(&path, "{}").unwrap();
+        let error = tracker.check_unchanged(&path, "{}").unwrap_err();
+        assert_eq!(error.code, ErrorCode::Conflict);
+
+        tracker.record(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let error = tracker.check_unchanged(&path, "{}").unwrap_err();
+        assert_eq!(error.details.unwrap()["onDisk"], Value::Null);
+    }
+}
@@ -0,0 +1,232 @@
+// Text encoding detection for locale files
+//
+// Locale files exported from Windows tools are often UTF-16 and may start with
+// a byte order mark, which `fs::read_to_string` either rejects or passes
+// through into the JSON. Files are decoded here instead: a BOM decides the
+// encoding when present, otherwise the zero bytes around the first character
+// give away UTF-16/32 (JSON always starts with an ASCII character), and
+// anything else must be UTF-8. The detected encoding is reported to the
+// frontend and reused when the file, or files generated from it, are written.
+use serde::Serialize;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+use crate::error::CommandError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Charset {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+    #[serde(rename = "utf-32le")]
+    Utf32Le,
+    #[serde(rename = "utf-32be")]
+    Utf32Be,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Encoding {
+    pub charset: Charset,
+    pub bom: bool,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Self {
+            charset: Charset::Utf8,
+            bom: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedText {
+    pub content: String,
+    pub encoding: Encoding,
+}
+
+// Checked in order, so the UTF-32 LE mark wins over the UTF-16 LE mark it starts with
+const BOMS: &[(&[u8], Charset)] = &[
+    (&[0xFF, 0xFE, 0x00, 0x00], Charset::Utf32Le),
+    (&[0x00, 0x00, 0xFE, 0xFF], Charset::Utf32Be),
+    (&[0xEF, 0xBB, 0xBF], Charset::Utf8),
+    (&[0xFF, 0xFE], Charset::Utf16Le),
+    (&[0xFE, 0xFF], Charset::Utf16Be),
+];
+
+pub fn detect(bytes: &[u8]) -> Encoding {
+    for (bom, charset) in BOMS {
+        if bytes.starts_with(bom) {
+            return Encoding {
+                charset: *charset,
+                bom: true,
+            };
+        }
+    }
+
+    let charset = match bytes {
+        [0, 0, 0, b, ..] if *b != 0 => Charset::Utf32Be,
+        [a, 0, 0, 0, ..] if *a != 0 => Charset::Utf32Le,
+        [0, b, ..] if *b != 0 => Charset::Utf16Be,
+        [a, 0, ..] if *a != 0 => Charset::Utf16Le,
+        _ => Charset::Utf8,
+    };
+
+    Encoding {
+        charset,
+        bom: false,
+    }
+}
+
+pub fn decode(bytes: &[u8]) -> Result<DecodedText, CommandError> {
+    let encoding = detect(bytes);
+    let body = if encoding.bom {
+        &bytes[bom_len(encoding.charset)..]
+    } else {
+        bytes
+    };
+
+    let content = match encoding.charset {
+        Charset::Utf8 => String::from_utf8(body.to_vec()).map_err(|e| {
+            CommandError::invalid_input("File is not valid UTF-8")
+                .with_details(json!({ "offset": e.utf8_error().valid_up_to() }))
+        })?,
+        Charset::Utf16Le | Charset::Utf16Be => {
+            if body.len() % 2 != 0 {
+                return Err(CommandError::invalid_input("File is not valid UTF-16"));
+            }
+            let units: Vec<u16> = body
+                .chunks_exact(2)
+                .map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if encoding.charset == Charset::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                })
+                .collect();
+            String::from_utf16(&units)
+                .map_err(|_| CommandError::invalid_input("File is not valid UTF-16"))?
+        }
+        Charset::Utf32Le | Charset::Utf32Be => {
+            if body.len() % 4 != 0 {
+                return Err(CommandError::invalid_input("File is not valid UTF-32"));
+            }
+            body.chunks_exact(4)
+                .map(|quad| {
+                    let quad = [quad[0], quad[1], quad[2], quad[3]];
+                    let code = if encoding.charset == Charset::Utf32Le {
+                        u32::from_le_bytes(quad)
+                    } else {
+                        u32::from_be_bytes(quad)
+                    };
+                    char::from_u32(code)
+                })
+                .collect::<Option<String>>()
+                .ok_or_else(|| CommandError::invalid_input("File is not valid UTF-32"))?
+        }
+    };
+
+    Ok(DecodedText { content, encoding })
+}
+
+pub fn encode(text: &str, encoding: Encoding) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len() + 4);
+    if encoding.bom {
+        let (bom, _) = BOMS
+            .iter()
+            .find(|(_, charset)| *charset == encoding.charset)
+            .expect("every charset has a BOM");
+        bytes.extend_from_slice(bom);
+    }
+
+    match encoding.charset {
+        Charset::Utf8 => bytes.extend_from_slice(text.as_bytes()),
+        Charset::Utf16Le => text
+            .encode_utf16()
+            .for_each(|unit| bytes.extend_from_slice(&unit.to_le_bytes())),
+        Charset::Utf16Be => text
+            .encode_utf16()
+            .for_each(|unit| bytes.extend_from_slice(&unit.to_be_bytes())),
+        Charset::Utf32Le => text
+            .chars()
+            .for_each(|c| bytes.extend_from_slice(&(c as u32).to_le_bytes())),
+        Charset::Utf32Be => text
+            .chars()
+            .for_each(|c| bytes.extend_from_slice(&(c as u32).to_be_bytes())),
+    }
+
+    bytes
+}
+
+// Read and decode a text file
+pub fn read_text(path: &Path) -> Result<DecodedText, CommandError> {
+    let bytes = fs::read(path).map_err(|e| CommandError::io("Failed to read file", e))?;
+    decode(&bytes)
+}
+
+fn bom_len(charset: Charset) -> usize {
+    match charset {
+        Charset::Utf8 => 3,
+        Charset::Utf16Le | Charset::Utf16Be => 2,
+        Charset::Utf32Le | Charset::Utf32Be => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "{\"title\": \"Größe 😀\"}\n";
+    const CHARSETS: [Charset; 5] = [
+        Charset::Utf8,
+        Charset::Utf16Le,
+        Charset::Utf16Be,
+        Charset::Utf32Le,
+        Charset::Utf32Be,
+    ];
+
+    #[test]
+    fn every_encoding_round_trips() {
+        for charset in CHARSETS {
+            for bom in [false, true] {
+                let encoding = Encoding { charset, bom };
+                let bytes = encode(TEXT, encoding);
+
+                let decoded = decode(&bytes).unwrap();
+                assert_eq!(decoded.encoding, encoding);
+                assert_eq!(decoded.content, TEXT, "{:?}", encoding);
+                assert_eq!(encode(&decoded.content, decoded.encoding), bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn byte_order_marks_are_written_and_stripped() {
+        let utf16 = Encoding {
+            charset: Charset::Utf16Le,
+            bom: true,
+        };
+        assert_eq!(encode("{}", utf16), [0xFF, 0xFE, b'{', 0, b'}', 0]);
+
+        let utf8 = decode(b"\xEF\xBB\xBF{}").unwrap();
+        assert_eq!(utf8.content, "{}");
+        assert!(utf8.encoding.bom);
+    }
+
+    #[test]
+    fn invalid_text_is_refused() {
+        let error = decode(b"{\"a\": \"\xFF\"}").unwrap_err();
+        assert_eq!(error.details.unwrap()["offset"], 7);
+
+        // An odd number of bytes can't be UTF-16
+        assert!(decode(&[0xFF, 0xFE, b'{', 0, b'}']).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// Error type shared by all Tauri commands
+//
+// Serialized as `{ code, message, details? }`. `code` is stable and is what the
+// frontend should branch on (and use to look up localized text); `message` is
+// an English fallback for logs and the UI, and `details` carries structured
+// context such as the offending path or a JSON error location.
+use serde::Serialize;
+use serde_json::json;
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ErrorCode {
+    NotFound,
+    AlreadyExists,
+    Conflict,
+    PermissionDenied,
+    InvalidJson,
+    InvalidInput,
+    InvalidKey,
+    PathOutsideScope,
+    VaultLocked,
+    WrongPassphrase,
+    WeakPassphrase,
+    Unavailable,
+    Io,
+    Internal,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    // Wrap an I/O error with context, keeping the cases the frontend cares
+    // about (missing file, no permission) distinguishable from generic I/O
+    pub fn io(context: &str, error: io::Error) -> Self {
+        let code = match error.kind() {
+            io::ErrorKind::NotFound => ErrorCode::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            io::ErrorKind::AlreadyExists => ErrorCode::AlreadyExists,
+            _ => ErrorCode::Io,
+        };
+
+        Self::new(code, format!("{}: {}", context, error))
+    }
+
+    // Wrap a serde_json error, reporting where in the input it happened
+    pub fn json(context: &str, error: serde_json::Error) -> Self {
+        Self::new(ErrorCode::InvalidJson, format!("{}: {}", context, error)).with_details(json!({
+            "line": error.line(),
+            "column": error.column(),
+        }))
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<tauri::Error> for CommandError {
+    fn from(error: tauri::Error) -> Self {
+        Self::internal(error.to_string())
+    }
+}
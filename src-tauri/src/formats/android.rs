@@ -0,0 +1,691 @@
+// Android string resources (`res/values/strings.xml`)
+//
+// Every translatable resource becomes a key named after it:
+//
+//   <string name="title">Inbox</string>         "title": "Inbox",
+//   <plurals name="messages">                   "messages": {
+//     <item quantity="one">%d message</item>      "one": "%d message",
+//     <item quantity="other">%d messages</item>   "other": "%d messages"
+//   </plurals>                                  },
+//   <string-array name="days">                  "days": ["Mon", "Tue"]
+//     <item>Mon</item>
+//     <item>Tue</item>
+//   </string-array>
+//
+// Values are unescaped the way aapt reads them (`\'`, `\"`, `\@`, `\n`,
+// `\uXXXX`, unescaped double quotes dropped, whitespace collapsed), and
+// escaped again when written. Format arguments such as `%1$s` are plain text.
+// Strings with markup (`<b>`, `<xliff:g>`) keep it as written, entities
+// included. Resources marked `translatable="false"` are left out.
+//
+// A translated file is the source file with the translatable resources
+// filled in and everything else inside `<resources>` (untranslatable
+// strings, colors, dimensions...) dropped, since Android falls back to the
+// default resources for those. Plurals get the quantities of the target
+// language. The file goes into the `values-<qualifier>` folder next to
+// `values`, see formats::default_template.
+use quick_xml::escape::partial_escape;
+use quick_xml::events::{BytesCData, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use super::xml::{attribute, is_whitespace, syntax_error, write};
+use super::{plurals, RenderOptions};
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::locale_json::{LoadWarning, LoadedLocaleFile};
+
+#[derive(Debug)]
+enum Node {
+    // An element directly inside `<resources>`, with everything in it
+    Resource(Vec<Event<'static>>),
+    Other(Event<'static>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    String,
+    Plurals,
+    StringArray,
+}
+
+#[derive(Debug)]
+struct Resource {
+    kind: Kind,
+    name: String,
+    start: BytesStart<'static>,
+    items: Vec<Item>,
+    // Whitespace in front of the first item and of the closing tag, for
+    // plurals and arrays
+    item_indent: Option<Event<'static>>,
+    closing_indent: Option<Event<'static>>,
+}
+
+#[derive(Debug)]
+struct Item {
+    // Plurals only
+    quantity: Option<String>,
+    text: Text,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Text {
+    value: String,
+    // Contains elements, e.g. `<b>` or `<xliff:g>`
+    markup: bool,
+    // Written as a CDATA section
+    cdata: bool,
+}
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    let mut tree = Map::new();
+    let mut duplicates = BTreeMap::new();
+
+    for node in parse(content)? {
+        let Node::Resource(events) = node else {
+            continue;
+        };
+        let Some(resource) = Resource::read(&events)? else {
+            continue;
+        };
+
+        let value = match resource.kind {
+            Kind::String => json!(resource
+                .items
+                .first()
+                .map(|item| item.text.value.as_str())
+                .unwrap_or_default()),
+            Kind::Plurals => Value::Object(
+                resource
+                    .items
+                    .iter()
+                    .map(|item| {
+                        let quantity = item.quantity.clone().unwrap_or_default();
+                        (quantity, json!(item.text.value))
+                    })
+                    .collect(),
+            ),
+            Kind::StringArray => resource
+                .items
+                .iter()
+                .map(|item| json!(item.text.value))
+                .collect(),
+        };
+        if tree.insert(resource.name.clone(), value).is_some() {
+            *duplicates.entry(resource.name).or_insert(1) += 1;
+        }
+    }
+
+    let warnings = duplicates
+        .into_iter()
+        .map(|(path, occurrences)| LoadWarning::DuplicateKey { path, occurrences })
+        .collect();
+    Ok(LoadedLocaleFile {
+        data: Value::Object(tree),
+        encoding,
+        warnings,
+        context: BTreeMap::new(),
+    })
+}
+
+pub fn render(source: &str, tree: &Value, options: &RenderOptions) -> Result<String, CommandError> {
+    let mut writer = Writer::new(Vec::new());
+    // Whitespace is held back until it's clear it isn't in front of a
+    // dropped resource
+    let mut whitespace: Option<Event<'static>> = None;
+
+    for node in parse(source)? {
+        match node {
+            Node::Resource(events) => {
+                let translated = match Resource::read(&events)? {
+                    Some(resource) => resource.translate(tree, options),
+                    None => None,
+                };
+                let pending = whitespace.take();
+                if let Some(translated) = translated {
+                    for event in pending.into_iter().chain(translated) {
+                        write(&mut writer, event)?;
+                    }
+                }
+            }
+            Node::Other(event) if is_whitespace(&event) => {
+                if let Some(pending) = whitespace.replace(event) {
+                    write(&mut writer, pending)?;
+                }
+            }
+            Node::Other(event) => {
+                if let Some(pending) = whitespace.take() {
+                    write(&mut writer, pending)?;
+                }
+                write(&mut writer, event)?;
+            }
+        }
+    }
+    if let Some(pending) = whitespace.take() {
+        write(&mut writer, pending)?;
+    }
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| CommandError::internal(format!("Written resources are not UTF-8: {}", e)))
+}
+
+fn parse(content: &str) -> Result<Vec<Node>, CommandError> {
+    let mut reader = Reader::from_str(content);
+    let mut nodes = Vec::new();
+    let mut depth = 0usize;
+    let mut resource: Vec<Event<'static>> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| {
+                syntax_error(
+                    "Android resource file",
+                    content,
+                    reader.buffer_position(),
+                    e,
+                )
+            })?
+            .into_owned();
+
+        if !resource.is_empty() {
+            match &event {
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                Event::Eof => return Err(CommandError::invalid_input("Unclosed Android resource")),
+                _ => {}
+            }
+            resource.push(event);
+            if depth == 1 {
+                nodes.push(Node::Resource(std::mem::take(&mut resource)));
+            }
+            continue;
+        }
+
+        match event {
+            Event::Start(_) if depth == 1 => {
+                depth += 1;
+                resource.push(event);
+                continue;
+            }
+            Event::Empty(_) if depth == 1 => {
+                nodes.push(Node::Resource(vec![event]));
+                continue;
+            }
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth = depth.saturating_sub(1),
+            Event::Eof => break,
+            _ => {}
+        }
+        nodes.push(Node::Other(event));
+    }
+
+    Ok(nodes)
+}
+
+impl Resource {
+    // The translatable resource in `events`, if it is one
+    fn read(events: &[Event<'static>]) -> Result<Option<Self>, CommandError> {
+        let (start, inner) = match events.split_first() {
+            Some((Event::Start(start), rest)) => (start, &rest[..rest.len().saturating_sub(1)]),
+            Some((Event::Empty(start), _)) => (start, &[][..]),
+            _ => return Ok(None),
+        };
+        let kind = match start.local_name().as_ref() {
+            b"string" => Kind::String,
+            b"plurals" => Kind::Plurals,
+            b"string-array" => Kind::StringArray,
+            _ => return Ok(None),
+        };
+        let Some(name) = attribute(start, b"name")? else {
+            return Ok(None);
+        };
+        if attribute(start, b"translatable")?.as_deref() == Some("false") {
+            return Ok(None);
+        }
+
+        let mut resource = Resource {
+            kind,
+            name,
+            start: start.clone(),
+            items: Vec::new(),
+            item_indent: None,
+            closing_indent: None,
+        };
+        if kind == Kind::String {
+            resource.items.push(Item {
+                quantity: None,
+                text: read_text(inner)?,
+            });
+            return Ok(Some(resource));
+        }
+
+        let mut index = 0;
+        while index < inner.len() {
+            match &inner[index] {
+                Event::Start(item) | Event::Empty(item)
+                    if item.local_name().as_ref() == b"item" =>
+                {
+                    let end = if matches!(inner[index], Event::Start(_)) {
+                        closing_index(inner, index)
+                    } else {
+                        index
+                    };
+                    let content = if end > index {
+                        &inner[index + 1..end]
+                    } else {
+                        &[][..]
+                    };
+                    resource.items.push(Item {
+                        quantity: attribute(item, b"quantity")?,
+                        text: read_text(content)?,
+                    });
+                    index = end;
+                }
+                event if is_whitespace(event) => {
+                    if resource.items.is_empty() {
+                        resource.item_indent = Some(event.clone());
+                    }
+                    resource.closing_indent = Some(event.clone());
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+        Ok(Some(resource))
+    }
+
+    // The events of the translated resource, or None if the tree has no
+    // translation for it
+    fn translate(&self, tree: &Value, options: &RenderOptions) -> Option<Vec<Event<'static>>> {
+        let value = tree.get(&self.name)?;
+        let items: Vec<(Option<String>, String)> = match self.kind {
+            Kind::String => vec![(None, value.as_str()?.to_string())],
+            Kind::Plurals => {
+                let forms = value.as_object()?;
+                let mut quantities: Vec<String> = match options.language {
+                    Some(language) => plurals::for_language(language)
+                        .categories
+                        .iter()
+                        .map(|category| category.to_string())
+                        .collect(),
+                    None => forms.keys().cloned().collect(),
+                };
+                // Android requires `other`
+                if !quantities.iter().any(|quantity| quantity == "other") {
+                    quantities.push("other".to_string());
+                }
+                quantities
+                    .into_iter()
+                    .map(|quantity| {
+                        let form = plurals::form(forms, &quantity);
+                        (Some(quantity), form)
+                    })
+                    .collect()
+            }
+            Kind::StringArray => value
+                .as_array()?
+                .iter()
+                .map(|item| (None, item.as_str().unwrap_or_default().to_string()))
+                .collect(),
+        };
+
+        let mut events = vec![Event::Start(self.start.clone())];
+        for (index, (quantity, value)) in items.iter().enumerate() {
+            let text = Text {
+                value: value.clone(),
+                ..self.source_text(index, quantity.as_deref())
+            };
+            if self.kind == Kind::String {
+                events.extend(write_text(&text));
+                continue;
+            }
+
+            events.extend(self.item_indent.clone());
+            let mut item = BytesStart::new("item");
+            if let Some(quantity) = quantity {
+                item.push_attribute(("quantity", quantity.as_str()));
+            }
+            events.push(Event::Start(item));
+            events.extend(write_text(&text));
+            events.push(Event::End(BytesEnd::new("item")));
+        }
+        if self.kind != Kind::String {
+            events.extend(self.closing_indent.clone());
+        }
+        let name = String::from_utf8_lossy(self.start.name().as_ref()).into_owned();
+        events.push(Event::End(BytesEnd::new(name)));
+        Some(events)
+    }
+
+    // The source item a translated item is written like: the one with the
+    // same quantity or position, or else the last
+    fn source_text(&self, index: usize, quantity: Option<&str>) -> Text {
+        let item = match quantity {
+            Some(quantity) => self
+                .items
+                .iter()
+                .find(|item| item.quantity.as_deref() == Some(quantity))
+                .or_else(|| {
+                    self.items
+                        .iter()
+                        .find(|item| item.quantity.as_deref() == Some("other"))
+                }),
+            None => self.items.get(index),
+        };
+        item.or_else(|| self.items.last())
+            .map(|item| item.text.clone())
+            .unwrap_or_default()
+    }
+}
+
+// Index of the event closing the element opened at `start`
+fn closing_index(events: &[Event], start: usize) -> usize {
+    let mut depth = 0usize;
+    for (index, event) in events.iter().enumerate().skip(start) {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    return index;
+                }
+            }
+            _ => {}
+        }
+    }
+    events.len()
+}
+
+fn read_text(events: &[Event]) -> Result<Text, CommandError> {
+    let markup = events
+        .iter()
+        .any(|event| matches!(event, Event::Start(_) | Event::Empty(_)));
+    let cdata = events.iter().any(|event| matches!(event, Event::CData(_)));
+
+    let mut value = String::new();
+    if markup {
+        // Elements as written, text unescaped for Android only
+        for event in events {
+            match event {
+                Event::Text(text) => value.push_str(&unescape(&String::from_utf8_lossy(text))),
+                event => {
+                    let mut writer = Writer::new(Vec::new());
+                    write(&mut writer, event.clone())?;
+                    value.push_str(&String::from_utf8_lossy(&writer.into_inner()));
+                }
+            }
+        }
+    } else {
+        let mut raw = String::new();
+        for event in events {
+            match event {
+                Event::Text(text) => {
+                    let text = text.unescape().map_err(|e| {
+                        CommandError::invalid_input(format!("Invalid resource text: {}", e))
+                    })?;
+                    raw.push_str(&text);
+                }
+                Event::CData(data) => raw.push_str(&String::from_utf8_lossy(data)),
+                _ => {}
+            }
+        }
+        value = unescape(&raw);
+    }
+
+    Ok(Text {
+        value,
+        markup,
+        cdata,
+    })
+}
+
+fn write_text(text: &Text) -> Option<Event<'static>> {
+    if text.value.is_empty() {
+        return None;
+    }
+    if text.markup {
+        return Some(Event::Text(BytesText::from_escaped(escape_markup(
+            &text.value,
+        ))));
+    }
+
+    let escaped = escape(&text.value);
+    if text.cdata && !escaped.contains("]]>") {
+        Some(Event::CData(BytesCData::new(escaped)))
+    } else {
+        Some(Event::Text(
+            BytesText::from_escaped(partial_escape(&escaped)).into_owned(),
+        ))
+    }
+}
+
+// Android's unescaping: backslash escapes, double quotes dropped, and runs of
+// whitespace outside them collapsed to one space
+fn unescape(text: &str) -> String {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    let mut quoted = false;
+    let mut in_whitespace = false;
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() && !quoted {
+            if !in_whitespace {
+                value.push(' ');
+            }
+            in_whitespace = true;
+            continue;
+        }
+        in_whitespace = false;
+
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(decoded) => value.push(decoded),
+                        None => {
+                            value.push_str("\\u");
+                            value.push_str(&hex);
+                        }
+                    }
+                }
+                Some(escaped) => value.push(escaped),
+                None => value.push('\\'),
+            },
+            c => value.push(c),
+        }
+    }
+
+    value
+}
+
+// The inverse of unescape. Values whose spaces would be collapsed are
+// wrapped in double quotes.
+fn escape(value: &str) -> String {
+    let escaped = escape_chars(value, true);
+    let keeps_spaces = value.starts_with(' ') || value.ends_with(' ') || value.contains("  ");
+    if keeps_spaces {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+// `at_start` if `text` begins the value, where `@` and `?` would make it a
+// resource reference
+fn escape_chars(text: &str, at_start: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '@' | '?' if at_start && index == 0 => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Escape the text between the tags of a string with markup. Entities are
+// kept, but a bare `&` is escaped so the file stays valid.
+fn escape_markup(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|end| start + end + 1) else {
+            break;
+        };
+        let text = escape_chars(&rest[..start], output.is_empty());
+        output.push_str(&escape_ampersands(&text));
+        output.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    let text = escape_chars(rest, output.is_empty()).replace('<', "&lt;");
+    output.push_str(&escape_ampersands(&text));
+    output
+}
+
+fn escape_ampersands(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for (index, c) in text.char_indices() {
+        let is_entity = c == '&'
+            && text[index + 1..].find(';').is_some_and(|end| {
+                let name = &text[index + 1..index + 1 + end];
+                !name.is_empty()
+                    && name
+                        .trim_start_matches('#')
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric())
+            });
+        if c == '&' && !is_entity {
+            output.push_str("&amp;");
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<resources>
+    <string name="app_name" translatable="false">Inbox</string>
+    <string name="greeting">Don\'t panic</string>
+    <string name="handle">\@inbox</string>
+    <string name="arrow">Next →</string>
+    <string name="summer">\u00e9t\u00e9</string>
+    <string name="padded">"  spaced  "</string>
+    <string name="collapsed">one
+        two</string>
+    <string name="bold">Tom &amp; <b>Jerry</b> &amp; friends</string>
+    <string name="data"><![CDATA[<i>raw</i>]]></string>
+    <color name="accent">#ff0000</color>
+    <plurals name="messages">
+        <item quantity="one">%d message</item>
+        <item quantity="other">%d messages</item>
+    </plurals>
+    <string-array name="days">
+        <item>Mon</item>
+        <item>Tue</item>
+    </string-array>
+</resources>
+"#;
+
+    fn russian() -> RenderOptions<'static> {
+        RenderOptions {
+            language: Some("ru_ru"),
+            needs_review: false,
+        }
+    }
+
+    #[test]
+    fn values_are_unescaped_like_aapt() {
+        let loaded = load(SOURCE, Encoding::default()).unwrap();
+
+        assert_eq!(
+            loaded.data,
+            json!({
+                "greeting": "Don't panic",
+                "handle": "@inbox",
+                "arrow": "Next →",
+                "summer": "été",
+                "padded": "  spaced  ",
+                "collapsed": "one two",
+                "bold": "Tom &amp; <b>Jerry</b> &amp; friends",
+                "data": "<i>raw</i>",
+                "messages": { "one": "%d message", "other": "%d messages" },
+                "days": ["Mon", "Tue"],
+            })
+        );
+    }
+
+    #[test]
+    fn values_survive_a_round_trip() {
+        let tree = load(SOURCE, Encoding::default()).unwrap().data;
+        let rendered = render(SOURCE, &tree, &RenderOptions::default()).unwrap();
+
+        assert_eq!(load(&rendered, Encoding::default()).unwrap().data, tree);
+        assert!(rendered.contains(r#"<string name="greeting">Don\'t panic</string>"#));
+        assert!(rendered.contains(r#"<string name="handle">\@inbox</string>"#));
+        assert!(rendered.contains(r#"<string name="padded">"  spaced  "</string>"#));
+        assert!(rendered.contains("<![CDATA[<i>raw</i>]]>"));
+        // Untranslatable strings and other resources fall back to the default
+        assert!(!rendered.contains("app_name"));
+        assert!(!rendered.contains("accent"));
+    }
+
+    #[test]
+    fn markup_keeps_entities_and_escapes_bare_ampersands() {
+        let tree = json!({ "bold": "Tom & <b>Jerry</b> &amp; friends" });
+        let rendered = render(SOURCE, &tree, &RenderOptions::default()).unwrap();
+
+        assert!(rendered
+            .contains(r#"<string name="bold">Tom &amp; <b>Jerry</b> &amp; friends</string>"#));
+    }
+
+    #[test]
+    fn plurals_get_the_target_quantities() {
+        let tree = json!({
+            "messages": {
+                "one": "%d сообщение",
+                "few": "%d сообщения",
+                "many": "%d сообщений",
+            },
+            "days": ["Пн", "Вт"],
+        });
+        let rendered = render(SOURCE, &tree, &russian()).unwrap();
+
+        assert!(rendered.contains(concat!(
+            "<plurals name=\"messages\">\n",
+            "        <item quantity=\"one\">%d сообщение</item>\n",
+            "        <item quantity=\"few\">%d сообщения</item>\n",
+            "        <item quantity=\"many\">%d сообщений</item>\n",
+            "        <item quantity=\"other\">%d сообщений</item>\n",
+            "    </plurals>"
+        )));
+        assert!(rendered.contains(concat!(
+            "<string-array name=\"days\">\n",
+            "        <item>Пн</item>\n",
+            "        <item>Вт</item>\n",
+            "    </string-array>"
+        )));
+        assert_eq!(
+            load(&rendered, Encoding::default()).unwrap().data["days"],
+            tree["days"]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+// Flutter Application Resource Bundles (`.arb`)
+//
+// An ARB file is JSON where `@`-prefixed entries are metadata rather than
+// messages:
+//
+//   {
+//     "@@locale": "en",
+//     "greeting": "Hello {name}",
+//     "@greeting": {
+//       "description": "Shown on the home screen",
+//       "placeholders": { "name": { "type": "String" } }
+//     }
+//   }
+//
+// Only the messages go into the tree; `@key` objects, `@@locale` and other
+// `@@` attributes are kept out of it, so they aren't translated. A message's
+// `description` is passed to the translator as context instead.
+//
+// A translated file is the source file with its messages replaced and
+// `@@locale` set to the target language, added first if the source has none.
+// Metadata is copied as is. Files are named like `app_de.arb`, see
+// formats::default_template; the locale uses the same spelling.
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use super::RenderOptions;
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::json_style::JsonStyle;
+use crate::locale_json::{self, LoadWarning, LoadedLocaleFile};
+use crate::project::languages::CodeStyle;
+
+const LOCALE_KEY: &str = "@@locale";
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    let loaded = locale_json::load(content, encoding)?;
+    let Value::Object(entries) = loaded.data else {
+        return Err(CommandError::invalid_input(
+            "An ARB file must be a JSON object",
+        ));
+    };
+
+    let mut context = BTreeMap::new();
+    for (key, value) in &entries {
+        let Some(message) = key.strip_prefix('@') else {
+            continue;
+        };
+        let description = value.get("description").and_then(Value::as_str);
+        if let Some(description) = description.filter(|description| !description.is_empty()) {
+            context.insert(message.to_string(), description.to_string());
+        }
+    }
+
+    let data: Map<String, Value> = entries
+        .into_iter()
+        .filter(|(key, _)| !key.starts_with('@'))
+        .collect();
+    let warnings = loaded
+        .warnings
+        .into_iter()
+        .filter(|warning| match warning {
+            LoadWarning::DuplicateKey { path, .. } => !path.starts_with('@'),
+        })
+        .collect();
+
+    Ok(LoadedLocaleFile {
+        data: Value::Object(data),
+        encoding: loaded.encoding,
+        warnings,
+        context,
+    })
+}
+
+pub fn render(source: &str, tree: &Value, options: &RenderOptions) -> Result<String, CommandError> {
+    let Value::Object(entries) = serde_json::from_str::<Value>(source)
+        .map_err(|e| CommandError::json("Failed to parse source ARB file", e))?
+    else {
+        return Err(CommandError::invalid_input(
+            "An ARB file must be a JSON object",
+        ));
+    };
+    let locale = options
+        .language
+        .map(|language| CodeStyle::Short.format(language));
+
+    let mut translated = Map::new();
+    if let Some(locale) = locale
+        .as_ref()
+        .filter(|_| !entries.contains_key(LOCALE_KEY))
+    {
+        translated.insert(LOCALE_KEY.to_string(), json!(locale));
+    }
+    for (key, value) in entries {
+        let value = if key == LOCALE_KEY {
+            match &locale {
+                Some(locale) => json!(locale),
+                None => value,
+            }
+        } else if key.starts_with('@') {
+            value
+        } else {
+            tree.get(&key).cloned().unwrap_or(value)
+        };
+        translated.insert(key, value);
+    }
+
+    JsonStyle::detect(source).render(&Value::Object(translated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{
+  "@@locale": "en",
+  "@@last_modified": "2026-10-01",
+  "greeting": "Hello {name}",
+  "@greeting": {
+    "description": "Shown on the home screen",
+    "placeholders": {
+      "name": {
+        "type": "String"
+      }
+    }
+  },
+  "logout": "Log out",
+  "@logout": {}
+}
+"#;
+
+    fn options(language: &str) -> RenderOptions<'_> {
+        RenderOptions {
+            language: Some(language),
+            needs_review: false,
+        }
+    }
+
+    #[test]
+    fn metadata_is_kept_out_of_the_tree_and_passed_as_context() {
+        let loaded = load(SOURCE, Encoding::default()).unwrap();
+
+        assert_eq!(
+            loaded.data,
+            json!({ "greeting": "Hello {name}", "logout": "Log out" })
+        );
+        assert_eq!(
+            loaded.context,
+            BTreeMap::from([(
+                "greeting".to_string(),
+                "Shown on the home screen".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn rendering_sets_the_locale_and_keeps_metadata() {
+        let tree = json!({ "greeting": "Olá {name}", "logout": "Sair" });
+        let rendered = render(SOURCE, &tree, &options("pt_br")).unwrap();
+
+        assert_eq!(
+            rendered,
+            r#"{
+  "@@locale": "pt_BR",
+  "@@last_modified": "2026-10-01",
+  "greeting": "Olá {name}",
+  "@greeting": {
+    "description": "Shown on the home screen",
+    "placeholders": {
+      "name": {
+        "type": "String"
+      }
+    }
+  },
+  "logout": "Sair",
+  "@logout": {}
+}
+"#
+        );
+    }
+
+    #[test]
+    fn a_missing_locale_is_added_first() {
+        let source = "{\n  \"title\": \"Inbox\"\n}\n";
+        let rendered = render(
+            source,
+            &json!({ "title": "Posteingang" }),
+            &options("de_de"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            rendered,
+            "{\n  \"@@locale\": \"de\",\n  \"title\": \"Posteingang\"\n}\n"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+// Locale file formats
+//
+// Every format is loaded as the JSON tree the app translates. Plain JSON is
+// handled by locale_json and json_style; other formats write a translated
+// tree by filling it into a copy of the source file, so the comments and
+// metadata the tree doesn't carry are kept. The format of a file is decided
+// by its extension. Formats holding every language in one file (string
+// catalogs) are translated in place instead.
+use serde_json::Value;
+use std::ffi::OsStr;
+use std::path::Path;
+
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::json_style::JsonStyle;
+use crate::locale_json::{self, LoadedLocaleFile};
+use crate::project::template;
+
+pub mod android;
+pub mod arb;
+pub mod plurals;
+pub mod po;
+pub mod strings;
+pub mod stringsdict;
+pub mod xcstrings;
+pub mod xliff;
+mod xml;
+
+// Offered in the source file dialog, after one filter for all of them
+pub const DIALOG_FILTERS: &[(&str, &[&str])] = &[
+    ("JSON Files", &["json"]),
+    ("Gettext Catalogs", &["po", "pot"]),
+    ("XLIFF Files", &["xlf", "xliff"]),
+    ("Android Resources", &["xml"]),
+    ("Apple Strings", &["strings", "stringsdict", "xcstrings"]),
+    ("Flutter ARB Files", &["arb"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Po,
+    Xliff,
+    Android,
+    Strings,
+    Stringsdict,
+    Xcstrings,
+    Arb,
+}
+
+#[derive(Debug, Default)]
+pub struct RenderOptions<'a> {
+    // Code of the target language, e.g. `de_de`
+    pub language: Option<&'a str>,
+    // Mark the translations for review, in formats that can
+    pub needs_review: bool,
+}
+
+impl Format {
+    // Anything unrecognized is treated as JSON
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "po" | "pot" => Self::Po,
+            "xlf" | "xliff" => Self::Xliff,
+            "xml" => Self::Android,
+            "strings" => Self::Strings,
+            "stringsdict" => Self::Stringsdict,
+            "xcstrings" => Self::Xcstrings,
+            "arb" => Self::Arb,
+            _ => Self::Json,
+        }
+    }
+
+    // Whether the source file also holds the translations, so every
+    // language is written into it rather than into a file of its own
+    pub fn writes_in_place(self) -> bool {
+        self == Self::Xcstrings
+    }
+}
+
+// Extension (with its dot) of the files translated from a source with
+// extension `ext`. Templates produce catalogs: `.pot` → `.po`.
+pub fn output_extension(ext: &str) -> &str {
+    if ext.eq_ignore_ascii_case(".pot") {
+        ".po"
+    } else {
+        ext
+    }
+}
+
+// Output path template used when none is configured for `source`, see
+// project/template.rs. Android and Apple look up translations in folders
+// next to the source's: `values-<qualifier>` and `<language>.lproj`. Flutter
+// expects `app_<locale>.arb` files.
+pub fn default_template(source: &Path) -> &'static str {
+    let folder = source
+        .parent()
+        .and_then(Path::file_name)
+        .and_then(OsStr::to_str)
+        .unwrap_or_default();
+
+    match Format::from_path(source) {
+        Format::Android if folder.starts_with("values") => {
+            "{dir}/../values-{lang-android}/{filename}"
+        }
+        Format::Strings | Format::Stringsdict if folder.ends_with(".lproj") => {
+            "{dir}/../{lang-bcp47}.lproj/{filename}"
+        }
+        Format::Arb => "{dir}/app_{lang-short}.arb",
+        _ => template::DEFAULT_TEMPLATE,
+    }
+}
+
+pub fn load(
+    format: Format,
+    content: &str,
+    encoding: Encoding,
+) -> Result<LoadedLocaleFile, CommandError> {
+    match format {
+        Format::Json => locale_json::load(content, encoding),
+        Format::Po => po::load(content, encoding),
+        Format::Xliff => xliff::load(content, encoding),
+        Format::Android => android::load(content, encoding),
+        Format::Strings => strings::load(content, encoding),
+        Format::Stringsdict => stringsdict::load(content, encoding),
+        Format::Xcstrings => xcstrings::load(content, encoding),
+        Format::Arb => arb::load(content, encoding),
+    }
+}
+
+// Write the translated `tree` in the format and layout of the `source` file
+pub fn render(
+    format: Format,
+    source: &str,
+    tree: &Value,
+    options: &RenderOptions,
+) -> Result<String, CommandError> {
+    match format {
+        Format::Json => JsonStyle::detect(source).render(tree),
+        Format::Po => po::render(source, tree, options),
+        Format::Xliff => xliff::render(source, tree, options),
+        Format::Android => android::render(source, tree, options),
+        Format::Strings => strings::render(source, tree),
+        Format::Stringsdict => stringsdict::render(source, tree, options),
+        Format::Xcstrings => xcstrings::render(source, tree, options),
+        Format::Arb => arb::render(source, tree, options),
+    }
+}
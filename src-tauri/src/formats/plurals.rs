@@ -0,0 +1,163 @@
+// Plural rules per language
+//
+// Translated plurals are stored in the tree as objects keyed by CLDR plural
+// category (`one`, `few`, `other`, ...). Formats that number their forms,
+// like gettext's `msgstr[n]`, map index n to `categories[n]`. The gettext
+// expressions are the ones shipped with GNU gettext and Unicode CLDR.
+use serde_json::{Map, Value};
+
+use crate::project::languages::normalize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluralRule {
+    // C expression selecting the form for `n`, as in a Plural-Forms header
+    pub expression: &'static str,
+    // CLDR category of each form, in gettext's order
+    pub categories: &'static [&'static str],
+}
+
+const ONE_FORM: PluralRule = PluralRule {
+    expression: "0",
+    categories: &["other"],
+};
+
+const ONE_OTHER: PluralRule = PluralRule {
+    expression: "(n != 1)",
+    categories: &["one", "other"],
+};
+
+const ONE_OTHER_WITH_ZERO: PluralRule = PluralRule {
+    expression: "(n > 1)",
+    categories: &["one", "other"],
+};
+
+const EAST_SLAVIC: PluralRule = PluralRule {
+    expression:
+        "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+    categories: &["one", "few", "many"],
+};
+
+// Same forms as EAST_SLAVIC, but CLDR calls the third one `other`
+const SERBO_CROATIAN: PluralRule = PluralRule {
+    expression:
+        "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+    categories: &["one", "few", "other"],
+};
+
+const POLISH: PluralRule = PluralRule {
+    expression: "(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2)",
+    categories: &["one", "few", "many"],
+};
+
+const WEST_SLAVIC: PluralRule = PluralRule {
+    expression: "(n==1 ? 0 : n>=2 && n<=4 ? 1 : 2)",
+    categories: &["one", "few", "other"],
+};
+
+const LITHUANIAN: PluralRule = PluralRule {
+    expression: "(n%10==1 && n%100!=11 ? 0 : n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2)",
+    categories: &["one", "few", "other"],
+};
+
+const LATVIAN: PluralRule = PluralRule {
+    expression: "(n%10==1 && n%100!=11 ? 0 : n != 0 ? 1 : 2)",
+    categories: &["one", "other", "zero"],
+};
+
+const ROMANIAN: PluralRule = PluralRule {
+    expression: "(n==1 ? 0 : (n==0 || (n%100 > 0 && n%100 < 20)) ? 1 : 2)",
+    categories: &["one", "few", "other"],
+};
+
+const SLOVENIAN: PluralRule = PluralRule {
+    expression: "(n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3)",
+    categories: &["one", "two", "few", "other"],
+};
+
+const IRISH: PluralRule = PluralRule {
+    expression: "(n==1 ? 0 : n==2 ? 1 : n<7 ? 2 : n<11 ? 3 : 4)",
+    categories: &["one", "two", "few", "many", "other"],
+};
+
+const ARABIC: PluralRule = PluralRule {
+    expression: "(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5)",
+    categories: &["zero", "one", "two", "few", "many", "other"],
+};
+
+impl PluralRule {
+    pub fn nplurals(&self) -> usize {
+        self.categories.len()
+    }
+
+    // Value of a gettext Plural-Forms header
+    pub fn plural_forms(&self) -> String {
+        format!("nplurals={}; plural={};", self.nplurals(), self.expression)
+    }
+}
+
+// Rule for an app language code (`de_de`) or a locale like `pt-BR`.
+// Languages not listed use `one`/`other` with `n != 1`.
+pub fn for_language(code: &str) -> PluralRule {
+    let code = normalize(code);
+    let language = code.split('_').next().unwrap_or_default();
+
+    match language {
+        "ja" | "ko" | "zh" | "vi" | "th" | "id" | "ms" | "lo" | "km" | "my" => ONE_FORM,
+        "fr" | "oc" | "ln" => ONE_OTHER_WITH_ZERO,
+        "pt" if code == "pt_br" => ONE_OTHER_WITH_ZERO,
+        "ru" | "uk" | "be" => EAST_SLAVIC,
+        "sr" | "hr" | "bs" => SERBO_CROATIAN,
+        "pl" => POLISH,
+        "cs" | "sk" => WEST_SLAVIC,
+        "lt" => LITHUANIAN,
+        "lv" => LATVIAN,
+        "ro" | "mo" => ROMANIAN,
+        "sl" => SLOVENIAN,
+        "ga" => IRISH,
+        "ar" => ARABIC,
+        _ => ONE_OTHER,
+    }
+}
+
+// The form for `category`, falling back to `other` for categories the
+// translation doesn't provide (e.g. `few` when translating from English)
+pub fn form(forms: &Map<String, Value>, category: &str) -> String {
+    forms
+        .get(category)
+        .or_else(|| forms.get("other"))
+        .or_else(|| forms.values().next_back())
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories_follow_cldr() {
+        assert_eq!(for_language("ru").categories, ["one", "few", "many"]);
+        assert_eq!(for_language("uk_ua").categories, ["one", "few", "many"]);
+        for code in ["hr", "sr", "bs_ba", "sr-Latn"] {
+            assert_eq!(
+                for_language(code).categories,
+                ["one", "few", "other"],
+                "{}",
+                code
+            );
+        }
+        assert_eq!(for_language("pl").categories, ["one", "few", "many"]);
+        assert_eq!(for_language("de_de").categories, ["one", "other"]);
+        assert_eq!(for_language("ja").categories, ["other"]);
+    }
+
+    #[test]
+    fn missing_forms_fall_back_to_other() {
+        let forms = serde_json::json!({ "one": "1 file", "other": "{n} files" });
+        let forms = forms.as_object().unwrap();
+
+        assert_eq!(form(forms, "one"), "1 file");
+        assert_eq!(form(forms, "few"), "{n} files");
+    }
+}
@@ -0,0 +1,809 @@
+// gettext PO catalogs and POT templates
+//
+// A catalog becomes a flat tree keyed by msgid. Entries with a msgctxt are
+// keyed `<msgctxt>\u0004<msgid>`, the way compiled .mo files store them, and
+// plural entries become an object of CLDR categories:
+//
+//   msgctxt "menu"                   "menu\u0004Open": "Open",
+//   msgid "Open"                     "%d file": {
+//   msgstr ""                          "one": "%d file",
+//                                      "other": "%d files"
+//   msgid "%d file"                  }
+//   msgid_plural "%d files"
+//   msgstr[0] ""
+//   msgstr[1] ""
+//
+// The text to translate is the msgstr where the catalog has a translation
+// that isn't marked fuzzy, and the msgid otherwise (always, for a POT file).
+// A translated catalog is the source catalog with its msgstrs replaced, its
+// `Language` and `Plural-Forms` headers set for the target language, and
+// fuzzy flags and previous msgids dropped (unless the translations need
+// review, which marks them fuzzy). Comments, references and obsolete (`#~`)
+// entries are kept. It is written in UTF-8, and its `Content-Type` says so,
+// which also replaces the `charset=CHARSET` placeholder of xgettext
+// templates that msgfmt refuses.
+//
+// An entry's extracted comments (`#.`) and references (`#:`) are passed to the
+// translator as context, since they are often the only hint of where and how
+// a short msgid is used.
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use super::{plurals, RenderOptions};
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::locale_json::{LoadWarning, LoadedLocaleFile};
+use crate::project::languages::CodeStyle;
+
+// Separates msgctxt from msgid in tree keys
+pub const CONTEXT_SEPARATOR: char = '\u{4}';
+
+// Written catalogs are always UTF-8
+const CONTENT_TYPE: &str = "text/plain; charset=UTF-8";
+
+// gettext wraps string literals and reference lines at this column
+const WRAP_WIDTH: usize = 79;
+
+#[derive(Debug, Clone, Default)]
+struct PoEntry {
+    // `# ` lines written by translators
+    translator_comments: Vec<String>,
+    // `#.` lines extracted from the source code
+    extracted_comments: Vec<String>,
+    // `#:` source locations, e.g. `src/views.py:42`
+    references: Vec<String>,
+    // `#,` flags, e.g. `fuzzy` or `python-format`
+    flags: Vec<String>,
+    // `#|` lines with the msgid a fuzzy translation was made for, verbatim
+    previous: Vec<String>,
+    context: Option<String>,
+    id: String,
+    id_plural: Option<String>,
+    // msgstr, or msgstr[0], msgstr[1], ... for plural entries
+    strings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Entry(PoEntry),
+    // Obsolete entries and comments that don't belong to an entry, verbatim
+    Verbatim(Vec<String>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Catalog {
+    items: Vec<Item>,
+}
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    let catalog = Catalog::parse(content)?;
+    let (data, warnings) = catalog.to_tree();
+    let context = catalog.context();
+
+    Ok(LoadedLocaleFile {
+        data,
+        encoding,
+        warnings,
+        context,
+    })
+}
+
+// The catalog for the target language, built from the `source` catalog or
+// template and the translated `tree`. Entries missing from the tree are
+// written untranslated. Without a language, the source's `Language` header
+// is kept.
+pub fn render(source: &str, tree: &Value, options: &RenderOptions) -> Result<String, CommandError> {
+    let rendered = Catalog::parse(source)?.translate(tree, options).render();
+
+    if source.contains("\r\n") {
+        Ok(rendered.replace('\n', "\r\n"))
+    } else {
+        Ok(rendered)
+    }
+}
+
+impl PoEntry {
+    fn key(&self) -> String {
+        match &self.context {
+            Some(context) => format!("{}{}{}", context, CONTEXT_SEPARATOR, self.id),
+            None => self.id.clone(),
+        }
+    }
+
+    // The entry holding the `Key: value` headers
+    fn is_header(&self) -> bool {
+        self.context.is_none() && self.id.is_empty()
+    }
+
+    fn is_fuzzy(&self) -> bool {
+        self.flags.iter().any(|flag| flag == "fuzzy")
+    }
+
+    fn add_comment(&mut self, line: &str) {
+        let text = |prefix_len: usize| {
+            let rest = &line[prefix_len..];
+            rest.strip_prefix(' ').unwrap_or(rest).to_string()
+        };
+
+        match line.get(..2) {
+            Some("#.") => self.extracted_comments.push(text(2)),
+            Some("#:") => self
+                .references
+                .extend(line[2..].split_whitespace().map(str::to_string)),
+            Some("#,") => self.flags.extend(
+                line[2..]
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|flag| !flag.is_empty())
+                    .map(str::to_string),
+            ),
+            Some("#|") => self.previous.push(line.to_string()),
+            _ => self.translator_comments.push(text(1)),
+        }
+    }
+}
+
+impl Catalog {
+    fn parse(text: &str) -> Result<Self, CommandError> {
+        let mut parser = Parser::default();
+        let mut number = 0;
+        for line in text.lines() {
+            number += 1;
+            parser.line(number, line.trim())?;
+        }
+        parser.finish(number)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &PoEntry> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Entry(entry) => Some(entry),
+            Item::Verbatim(_) => None,
+        })
+    }
+
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut PoEntry> {
+        self.items.iter_mut().filter_map(|item| match item {
+            Item::Entry(entry) => Some(entry),
+            Item::Verbatim(_) => None,
+        })
+    }
+
+    // Extracted comments and references by tree key, for entries that have any
+    fn context(&self) -> BTreeMap<String, String> {
+        let mut context = BTreeMap::new();
+        for entry in self.entries().filter(|entry| !entry.is_header()) {
+            let mut notes = entry.extracted_comments.clone();
+            if !entry.references.is_empty() {
+                notes.push(format!("References: {}", entry.references.join(", ")));
+            }
+            if !notes.is_empty() {
+                context.insert(entry.key(), notes.join("; "));
+            }
+        }
+        context
+    }
+
+    // A header such as `Language`, read from the header entry's msgstr
+    fn header_field(&self, name: &str) -> Option<String> {
+        let header = self.entries().find(|entry| entry.is_header())?;
+        header.strings.first()?.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+    }
+
+    fn set_header_field(&mut self, name: &str, value: &str) {
+        if !self.entries().any(|entry| entry.is_header()) {
+            self.items.insert(
+                0,
+                Item::Entry(PoEntry {
+                    strings: vec![String::new()],
+                    ..PoEntry::default()
+                }),
+            );
+        }
+        let Some(header) = self.entries_mut().find(|entry| entry.is_header()) else {
+            return;
+        };
+        if header.strings.is_empty() {
+            header.strings.push(String::new());
+        }
+
+        let mut found = false;
+        let mut updated = String::new();
+        for line in header.strings[0].split_inclusive('\n') {
+            let matches = line
+                .split_once(':')
+                .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case(name));
+            if matches {
+                updated.push_str(&format!("{}: {}\n", name, value));
+                found = true;
+            } else {
+                updated.push_str(line);
+            }
+        }
+        if !found {
+            if !updated.is_empty() && !updated.ends_with('\n') {
+                updated.push('\n');
+            }
+            updated.push_str(&format!("{}: {}\n", name, value));
+        }
+        header.strings[0] = updated;
+    }
+
+    fn to_tree(&self) -> (Value, Vec<LoadWarning>) {
+        // Names the forms of plural translations already in the catalog
+        let rule = self
+            .header_field("Language")
+            .filter(|language| !language.is_empty())
+            .map(|language| plurals::for_language(&language));
+
+        let mut tree = Map::new();
+        let mut duplicates = BTreeMap::new();
+        for entry in self.entries().filter(|entry| !entry.is_header()) {
+            let translated = !entry.is_fuzzy()
+                && !entry.strings.is_empty()
+                && entry.strings.iter().all(|string| !string.is_empty());
+
+            let value = match &entry.id_plural {
+                None if translated => json!(entry.strings[0]),
+                None => json!(entry.id),
+                Some(id_plural) => match rule
+                    .filter(|rule| translated && rule.categories.len() == entry.strings.len())
+                {
+                    Some(rule) => Value::Object(
+                        rule.categories
+                            .iter()
+                            .zip(&entry.strings)
+                            .map(|(category, string)| (category.to_string(), json!(string)))
+                            .collect(),
+                    ),
+                    None => json!({ "one": entry.id, "other": id_plural }),
+                },
+            };
+
+            let key = entry.key();
+            if tree.insert(key.clone(), value).is_some() {
+                *duplicates.entry(key).or_insert(1) += 1;
+            }
+        }
+
+        let warnings = duplicates
+            .into_iter()
+            .map(|(path, occurrences)| LoadWarning::DuplicateKey { path, occurrences })
+            .collect();
+        (Value::Object(tree), warnings)
+    }
+
+    fn translate(&self, tree: &Value, options: &RenderOptions) -> Self {
+        let language = options.language;
+        let mut catalog = self.clone();
+        let rule = plurals::for_language(
+            &language
+                .map(str::to_string)
+                .or_else(|| self.header_field("Language"))
+                .unwrap_or_default(),
+        );
+
+        if let Some(language) = language {
+            catalog.set_header_field("Language", &CodeStyle::UnderscoreRegion.format(language));
+        }
+        catalog.set_header_field("Plural-Forms", &rule.plural_forms());
+        catalog.set_header_field("Content-Type", CONTENT_TYPE);
+
+        for entry in catalog.entries_mut() {
+            // The source's fuzzy markers don't apply to the new translations
+            entry.flags.retain(|flag| flag != "fuzzy");
+            entry.previous.clear();
+            if entry.is_header() {
+                continue;
+            }
+
+            let translation = tree.get(entry.key());
+            if translation.is_some() && options.needs_review {
+                entry.flags.push("fuzzy".to_string());
+            }
+            entry.strings = match (&entry.id_plural, translation) {
+                (None, Some(Value::String(string))) => vec![string.clone()],
+                (None, _) => vec![String::new()],
+                (Some(_), Some(Value::Object(forms))) => rule
+                    .categories
+                    .iter()
+                    .map(|category| plurals::form(forms, category))
+                    .collect(),
+                (Some(_), _) => vec![String::new(); rule.categories.len()],
+            };
+        }
+
+        catalog
+    }
+
+    fn render(&self) -> String {
+        let mut output = String::new();
+        for (index, item) in self.items.iter().enumerate() {
+            if index > 0 {
+                output.push('\n');
+            }
+            match item {
+                Item::Entry(entry) => write_entry(&mut output, entry),
+                Item::Verbatim(lines) => {
+                    for line in lines {
+                        output.push_str(line);
+                        output.push('\n');
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Str(usize),
+}
+
+#[derive(Default)]
+struct Parser {
+    items: Vec<Item>,
+    // Comment lines waiting for the entry they belong to
+    comments: Vec<String>,
+    // Lines of the obsolete entry being read
+    obsolete: Vec<String>,
+    entry: Option<PoEntry>,
+    // Field that continuation strings are appended to
+    field: Option<Field>,
+}
+
+impl Parser {
+    fn line(&mut self, number: usize, line: &str) -> Result<(), CommandError> {
+        if line.is_empty() {
+            self.finish_entry(number)?;
+            self.finish_obsolete();
+            return Ok(());
+        }
+
+        if line.starts_with("#~") {
+            self.finish_entry(number)?;
+            // Comments right above an obsolete entry belong to it
+            self.obsolete.append(&mut self.comments);
+            self.obsolete.push(line.to_string());
+            return Ok(());
+        }
+        self.finish_obsolete();
+
+        if line.starts_with('#') {
+            // A comment after a msgstr starts the next entry
+            if matches!(self.field, Some(Field::Str(_))) {
+                self.finish_entry(number)?;
+            }
+            self.comments.push(line.to_string());
+            return Ok(());
+        }
+
+        if line.starts_with('"') {
+            let value = unquote(line).map_err(|reason| syntax_error(number, &reason))?;
+            let target = match (self.field, self.entry.as_mut()) {
+                (Some(field), Some(entry)) => field_mut(entry, field),
+                _ => None,
+            };
+            let target = target.ok_or_else(|| syntax_error(number, "String without a keyword"))?;
+            target.push_str(&value);
+            return Ok(());
+        }
+
+        let (keyword, rest) = line
+            .split_once(|c: char| c.is_whitespace())
+            .ok_or_else(|| syntax_error(number, "Expected a keyword followed by a string"))?;
+        let value = unquote(rest.trim()).map_err(|reason| syntax_error(number, &reason))?;
+        let field = match keyword {
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgid_plural" => Field::IdPlural,
+            "msgstr" => Field::Str(0),
+            _ => keyword
+                .strip_prefix("msgstr[")
+                .and_then(|rest| rest.strip_suffix(']'))
+                .and_then(|index| index.parse().ok())
+                .map(Field::Str)
+                .ok_or_else(|| syntax_error(number, &format!("Unknown keyword '{}'", keyword)))?,
+        };
+
+        // msgctxt, or a msgid not preceded by one, starts a new entry
+        let starts_entry = match field {
+            Field::Context => true,
+            Field::Id => self.field != Some(Field::Context),
+            _ => false,
+        };
+        if starts_entry {
+            self.finish_entry(number)?;
+            let mut entry = PoEntry::default();
+            for comment in self.comments.drain(..) {
+                entry.add_comment(&comment);
+            }
+            self.entry = Some(entry);
+        }
+
+        let expected = match (self.field, field) {
+            (_, Field::Context) | (None, Field::Id) | (Some(Field::Context), Field::Id) => true,
+            (Some(Field::Id), Field::IdPlural) => true,
+            (Some(Field::Id), Field::Str(0)) => keyword == "msgstr",
+            (Some(Field::IdPlural), Field::Str(0)) => keyword != "msgstr",
+            (Some(Field::Str(previous)), Field::Str(index)) => {
+                index == previous + 1 && keyword != "msgstr"
+            }
+            _ => false,
+        };
+        let entry = match self.entry.as_mut() {
+            Some(entry) if expected => entry,
+            _ => return Err(syntax_error(number, &format!("Unexpected '{}'", keyword))),
+        };
+
+        match field {
+            Field::Context => entry.context = Some(value),
+            Field::Id => entry.id = value,
+            Field::IdPlural => entry.id_plural = Some(value),
+            Field::Str(_) => entry.strings.push(value),
+        }
+        self.field = Some(field);
+        Ok(())
+    }
+
+    fn finish_entry(&mut self, number: usize) -> Result<(), CommandError> {
+        if let Some(entry) = self.entry.take() {
+            if entry.strings.is_empty() {
+                return Err(syntax_error(number, "Entry has no msgstr"));
+            }
+            self.items.push(Item::Entry(entry));
+        }
+        self.field = None;
+        Ok(())
+    }
+
+    fn finish_obsolete(&mut self) {
+        if !self.obsolete.is_empty() {
+            self.items
+                .push(Item::Verbatim(std::mem::take(&mut self.obsolete)));
+        }
+    }
+
+    // `last_line` is the number of the file's last line
+    fn finish(mut self, last_line: usize) -> Result<Catalog, CommandError> {
+        self.finish_entry(last_line)?;
+        self.finish_obsolete();
+        if !self.comments.is_empty() {
+            self.items
+                .push(Item::Verbatim(std::mem::take(&mut self.comments)));
+        }
+
+        Ok(Catalog { items: self.items })
+    }
+}
+
+fn field_mut(entry: &mut PoEntry, field: Field) -> Option<&mut String> {
+    match field {
+        Field::Context => entry.context.as_mut(),
+        Field::Id => Some(&mut entry.id),
+        Field::IdPlural => entry.id_plural.as_mut(),
+        Field::Str(_) => entry.strings.last_mut(),
+    }
+}
+
+fn write_entry(output: &mut String, entry: &PoEntry) {
+    for comment in &entry.translator_comments {
+        if comment.is_empty() {
+            output.push_str("#\n");
+        } else {
+            output.push_str(&format!("# {}\n", comment));
+        }
+    }
+    for comment in &entry.extracted_comments {
+        output.push_str(&format!("#. {}\n", comment));
+    }
+    if !entry.references.is_empty() {
+        let mut line = String::from("#:");
+        for reference in &entry.references {
+            if line.len() > 2 && line.len() + 1 + reference.len() > WRAP_WIDTH {
+                output.push_str(&line);
+                output.push('\n');
+                line = String::from("#:");
+            }
+            line.push(' ');
+            line.push_str(reference);
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    if !entry.flags.is_empty() {
+        output.push_str(&format!("#, {}\n", entry.flags.join(", ")));
+    }
+    for line in &entry.previous {
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    if let Some(context) = &entry.context {
+        write_field(output, "msgctxt", context);
+    }
+    write_field(output, "msgid", &entry.id);
+    match &entry.id_plural {
+        Some(id_plural) => {
+            write_field(output, "msgid_plural", id_plural);
+            for (index, string) in entry.strings.iter().enumerate() {
+                write_field(output, &format!("msgstr[{}]", index), string);
+            }
+        }
+        None => write_field(
+            output,
+            "msgstr",
+            entry.strings.first().map_or("", String::as_str),
+        ),
+    }
+}
+
+// Written the way msgmerge does: on one line if it fits and has no inner
+// newline, otherwise as `""` followed by one string per line, wrapped at
+// spaces
+fn write_field(output: &mut String, keyword: &str, value: &str) {
+    let single_line = format!("{} \"{}\"", keyword, escape(value));
+    let multiline = value.trim_end_matches('\n').contains('\n');
+    if !multiline && single_line.chars().count() <= WRAP_WIDTH {
+        output.push_str(&single_line);
+        output.push('\n');
+        return;
+    }
+
+    output.push_str(keyword);
+    output.push_str(" \"\"\n");
+    for segment in value.split_inclusive('\n') {
+        for line in wrap(&escape(segment), WRAP_WIDTH - 2) {
+            output.push('"');
+            output.push_str(&line);
+            output.push_str("\"\n");
+        }
+    }
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_inclusive(' ') {
+        if !current.is_empty() && current.chars().count() + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{7}' => escaped.push_str("\\a"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{b}' => escaped.push_str("\\v"),
+            '\u{c}' => escaped.push_str("\\f"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// The contents of a C string literal, e.g. `"Hello\n"`
+fn unquote(text: &str) -> Result<String, String> {
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(|| "Expected a quoted string".to_string())?;
+
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Err("Unescaped quote inside a string".to_string()),
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| "String ends with a backslash".to_string())?;
+                match escaped {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    'a' => value.push('\u{7}'),
+                    'b' => value.push('\u{8}'),
+                    'v' => value.push('\u{b}'),
+                    'f' => value.push('\u{c}'),
+                    '\\' | '"' | '\'' | '?' => value.push(escaped),
+                    '0'..='7' => {
+                        // Up to three octal digits
+                        let mut code = escaped.to_digit(8).unwrap_or(0);
+                        for _ in 0..2 {
+                            match chars.peek().and_then(|next| next.to_digit(8)) {
+                                Some(digit) => {
+                                    code = code * 8 + digit;
+                                    chars.next();
+                                }
+                                None => break,
+                            }
+                        }
+                        value.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    other => return Err(format!("Unknown escape '\\{}'", other)),
+                }
+            }
+            c => value.push(c),
+        }
+    }
+    Ok(value)
+}
+
+fn syntax_error(line: usize, reason: &str) -> CommandError {
+    CommandError::invalid_input(format!("Invalid PO file at line {}: {}", line, reason))
+        .with_details(json!({ "line": line }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comments_and_references_become_context() {
+        let content = r#"msgid ""
+msgstr ""
+"Language: de\n"
+
+# Reviewed by the docs team
+#. Button that opens a saved document
+#: src/menu.py:12 src/toolbar.py:40
+msgctxt "menu"
+msgid "Open"
+msgstr ""
+
+#: src/status.py:8
+msgid "Ready"
+msgstr ""
+
+msgid "Close"
+msgstr ""
+"#;
+
+        let loaded = load(content, Encoding::default()).unwrap();
+        assert_eq!(
+            loaded.context.get("menu\u{4}Open").map(String::as_str),
+            Some(
+                "Button that opens a saved document; References: src/menu.py:12, src/toolbar.py:40"
+            )
+        );
+        assert_eq!(
+            loaded.context.get("Ready").map(String::as_str),
+            Some("References: src/status.py:8")
+        );
+        assert!(!loaded.context.contains_key("Close"));
+        assert!(!loaded.context.contains_key(""));
+    }
+
+    const TEMPLATE: &str = r#"# Example project
+msgid ""
+msgstr ""
+"Project-Id-Version: example 1.0\n"
+"Content-Type: text/plain; charset=CHARSET\n"
+"Content-Transfer-Encoding: 8bit\n"
+"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\n"
+
+#: src/menu.py:12
+msgctxt "menu"
+msgid "Open"
+msgstr ""
+
+#: src/files.py:30
+#, python-format
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] ""
+msgstr[1] ""
+
+#, fuzzy
+#| msgid "Save changes"
+msgid "Save"
+msgstr "Sichern"
+"#;
+
+    #[test]
+    fn templates_load_their_source_text() {
+        let loaded = load(TEMPLATE, Encoding::default()).unwrap();
+
+        assert_eq!(
+            loaded.data,
+            json!({
+                "menu\u{4}Open": "Open",
+                "%d file": { "one": "%d file", "other": "%d files" },
+                // Fuzzy translations aren't trusted
+                "Save": "Save",
+            })
+        );
+        assert!(loaded.warnings.is_empty());
+    }
+
+    #[test]
+    fn translations_load_with_the_catalog_plural_forms() {
+        let content = r#"msgid ""
+msgstr ""
+"Language: ru\n"
+
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] "%d файл"
+msgstr[1] "%d файла"
+msgstr[2] "%d файлов"
+
+msgid "Open"
+msgstr "Открыть"
+
+msgid "Open"
+msgstr "Открыть"
+"#;
+
+        let loaded = load(content, Encoding::default()).unwrap();
+        assert_eq!(
+            loaded.data,
+            json!({
+                "%d file": { "one": "%d файл", "few": "%d файла", "many": "%d файлов" },
+                "Open": "Открыть",
+            })
+        );
+        assert_eq!(loaded.warnings.len(), 1);
+    }
+
+    #[test]
+    fn rendering_a_template_fills_in_the_target_language() {
+        let tree = json!({
+            "menu\u{4}Open": "Открыть",
+            "%d file": { "one": "%d файл", "few": "%d файла", "many": "%d файлов" },
+            "Save": "Сохранить",
+        });
+        let options = RenderOptions {
+            language: Some("ru_ru"),
+            needs_review: false,
+        };
+        let rendered = render(TEMPLATE, &tree, &options).unwrap();
+
+        assert!(rendered.contains("\"Language: ru_RU\\n\""));
+        assert!(rendered.contains("\"Content-Type: text/plain; charset=UTF-8\\n\""));
+        assert!(!rendered.contains("CHARSET"));
+        assert!(rendered.contains("\"Plural-Forms: nplurals=3; plural="));
+        assert!(rendered.contains("msgctxt \"menu\"\nmsgid \"Open\"\nmsgstr \"Открыть\""));
+        assert!(rendered
+            .contains("msgstr[0] \"%d файл\"\nmsgstr[1] \"%d файла\"\nmsgstr[2] \"%d файлов\""));
+        // The source's fuzzy flag and previous msgid are dropped, other flags,
+        // comments and references kept
+        assert!(!rendered.contains("fuzzy"));
+        assert!(!rendered.contains("#|"));
+        assert!(rendered.contains("#, python-format"));
+        assert!(rendered.contains("#: src/menu.py:12"));
+        assert!(rendered.starts_with("# Example project\n"));
+
+        // The rendered catalog loads back to the same tree
+        assert_eq!(load(&rendered, Encoding::default()).unwrap().data, tree);
+
+        let review = RenderOptions {
+            needs_review: true,
+            ..options
+        };
+        let rendered = render(TEMPLATE, &tree, &review).unwrap();
+        assert_eq!(rendered.matches("fuzzy").count(), 3);
+    }
+}
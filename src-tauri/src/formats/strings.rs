@@ -0,0 +1,283 @@
+// Apple `.strings` files (`Localizable.strings`)
+//
+// Every `"key" = "value";` pair becomes a flat key in the tree. Keys may also
+// be unquoted words, as in old-style property lists. Files are often UTF-16;
+// encoding.rs takes care of that, and translated files are written in the
+// source's encoding.
+//
+// A translated file is the source file with only the values replaced, so
+// comments, blank lines and the order of the entries are kept. Entries the
+// tree has no translation for keep the source text.
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::locale_json::{LoadWarning, LoadedLocaleFile};
+
+#[derive(Debug)]
+struct Entry {
+    key: String,
+    value: String,
+    // Byte range of the value token in the file, quotes included
+    span: Range<usize>,
+}
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    let mut tree = Map::new();
+    let mut duplicates = BTreeMap::new();
+
+    for entry in parse(content)? {
+        if tree.insert(entry.key.clone(), json!(entry.value)).is_some() {
+            *duplicates.entry(entry.key).or_insert(1) += 1;
+        }
+    }
+
+    let warnings = duplicates
+        .into_iter()
+        .map(|(path, occurrences)| LoadWarning::DuplicateKey { path, occurrences })
+        .collect();
+    Ok(LoadedLocaleFile {
+        data: Value::Object(tree),
+        encoding,
+        warnings,
+        context: BTreeMap::new(),
+    })
+}
+
+pub fn render(source: &str, tree: &Value) -> Result<String, CommandError> {
+    let mut output = String::with_capacity(source.len());
+    let mut copied = 0;
+
+    for entry in parse(source)? {
+        let Some(value) = tree.get(&entry.key).and_then(Value::as_str) else {
+            continue;
+        };
+        output.push_str(&source[copied..entry.span.start]);
+        output.push('"');
+        output.push_str(&escape(value));
+        output.push('"');
+        copied = entry.span.end;
+    }
+    output.push_str(&source[copied..]);
+
+    Ok(output)
+}
+
+fn parse(content: &str) -> Result<Vec<Entry>, CommandError> {
+    let mut parser = Parser {
+        content,
+        position: 0,
+    };
+    let mut entries = Vec::new();
+
+    loop {
+        parser.skip_trivia()?;
+        if parser.peek().is_none() {
+            break;
+        }
+
+        let (key, _) = parser.token()?;
+        parser.skip_trivia()?;
+        parser.expect('=')?;
+        parser.skip_trivia()?;
+        let (value, span) = parser.token()?;
+        parser.skip_trivia()?;
+        parser.expect(';')?;
+
+        entries.push(Entry { key, value, span });
+    }
+
+    Ok(entries)
+}
+
+struct Parser<'a> {
+    content: &'a str,
+    // Byte offset of the next character
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.content[self.position..].chars().next()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    // Skip whitespace, `/* */` and `//` comments
+    fn skip_trivia(&mut self) -> Result<(), CommandError> {
+        loop {
+            let rest = &self.content[self.position..];
+            if rest.starts_with("/*") {
+                let end = rest
+                    .find("*/")
+                    .ok_or_else(|| self.error("Unclosed comment"))?;
+                self.position += end + 2;
+            } else if rest.starts_with("//") {
+                self.position += rest.find('\n').unwrap_or(rest.len());
+            } else if rest.starts_with(|c: char| c.is_whitespace() || c == '\u{feff}') {
+                self.advance();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), CommandError> {
+        match self.advance() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.error(&format!("Expected '{}', found '{}'", expected, c))),
+            None => Err(self.error(&format!(
+                "Expected '{}' before the end of the file",
+                expected
+            ))),
+        }
+    }
+
+    // A quoted string or an unquoted word, with its byte range
+    fn token(&mut self) -> Result<(String, Range<usize>), CommandError> {
+        let start = self.position;
+        if self.peek() != Some('"') {
+            while self.peek().is_some_and(is_word_char) {
+                self.advance();
+            }
+            if self.position == start {
+                return Err(self.error("Expected a quoted string"));
+            }
+            let word = self.content[start..self.position].to_string();
+            return Ok((word, start..self.position));
+        }
+
+        self.advance();
+        let mut value = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => break,
+                Some('\\') => match self.advance() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('0') => value.push('\0'),
+                    Some('U') | Some('u') => value.push(self.unicode_escape()?),
+                    Some(c) => value.push(c),
+                    None => return Err(self.error("Unterminated string")),
+                },
+                Some(c) => value.push(c),
+                None => return Err(self.error("Unterminated string")),
+            }
+        }
+        Ok((value, start..self.position))
+    }
+
+    // The character of a `\UXXXX` escape, joining UTF-16 surrogate pairs
+    fn unicode_escape(&mut self) -> Result<char, CommandError> {
+        let unit = self.hex4()?;
+        if (0xD800..0xDC00).contains(&unit) && self.content[self.position..].starts_with("\\U") {
+            self.position += 2;
+            let low = self.hex4()?;
+            let code = 0x10000 + ((unit - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+            return char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"));
+        }
+        char::from_u32(unit).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, CommandError> {
+        let digits = self
+            .content
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("Invalid unicode escape"))?;
+        let unit =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("Invalid unicode escape"))?;
+        self.position += 4;
+        Ok(unit)
+    }
+
+    fn error(&self, reason: &str) -> CommandError {
+        let line = self.content[..self.position].matches('\n').count() + 1;
+        CommandError::invalid_input(format!("Invalid strings file at line {}: {}", line, reason))
+            .with_details(json!({ "line": line }))
+    }
+}
+
+// Characters of an unquoted key
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '$' | ':' | '/' | '-')
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{self, Charset};
+
+    const SOURCE: &str = r#"/* Title of the inbox screen */
+"inbox.title" = "Inbox";
+
+// Shown when a message can't be sent
+"send.failed" = "Couldn't send \"%@\".\nTry again.";
+legacy_key = "Legacy";
+"untranslated" = "Keep me";
+"#;
+
+    #[test]
+    fn utf16_files_round_trip_with_their_comments() {
+        let utf16 = Encoding {
+            charset: Charset::Utf16Le,
+            bom: true,
+        };
+        let decoded = encoding::decode(&encoding::encode(SOURCE, utf16)).unwrap();
+        assert_eq!(decoded.encoding, utf16);
+
+        let loaded = load(&decoded.content, decoded.encoding).unwrap();
+        assert_eq!(
+            loaded.data,
+            json!({
+                "inbox.title": "Inbox",
+                "send.failed": "Couldn't send \"%@\".\nTry again.",
+                "legacy_key": "Legacy",
+                "untranslated": "Keep me",
+            })
+        );
+
+        let tree = json!({
+            "inbox.title": "Posteingang",
+            "send.failed": "„%@“ konnte nicht gesendet werden.\nNoch einmal versuchen.",
+            "legacy_key": "Alt",
+        });
+        let rendered = render(&decoded.content, &tree).unwrap();
+        assert_eq!(
+            rendered,
+            r#"/* Title of the inbox screen */
+"inbox.title" = "Posteingang";
+
+// Shown when a message can't be sent
+"send.failed" = "„%@“ konnte nicht gesendet werden.\nNoch einmal versuchen.";
+legacy_key = "Alt";
+"untranslated" = "Keep me";
+"#
+        );
+
+        let written = encoding::encode(&rendered, decoded.encoding);
+        assert!(written.starts_with(&[0xFF, 0xFE]));
+        assert_eq!(encoding::decode(&written).unwrap().content, rendered);
+    }
+}
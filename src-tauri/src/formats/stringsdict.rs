@@ -0,0 +1,237 @@
+// Apple `.stringsdict` plural rules
+//
+// A `.stringsdict` file is a property list mapping each key to a format
+// string and the variables it references, each with one string per plural
+// category. A key becomes an object holding both:
+//
+//   <key>files_selected</key>                      "files_selected": {
+//   <dict>                                           "NSStringLocalizedFormatKey": "%#@files@",
+//     <key>NSStringLocalizedFormatKey</key>          "files": {
+//     <string>%#@files@</string>                       "one": "%d file",
+//     <key>files</key>                                 "other": "%d files"
+//     <dict>                                         }
+//       <key>NSStringFormatSpecTypeKey</key>       }
+//       <string>NSStringPluralRuleType</string>
+//       <key>NSStringFormatValueTypeKey</key>
+//       <string>d</string>
+//       <key>one</key>
+//       <string>%d file</string>
+//       <key>other</key>
+//       <string>%d files</string>
+//     </dict>
+//   </dict>
+//
+// A translated file is the source file with the format strings replaced and
+// every variable given the plural categories of the target language. It is
+// written the way Xcode writes property lists.
+use plist::{Dictionary, Value as Plist};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use super::{plurals, RenderOptions};
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::locale_json::LoadedLocaleFile;
+
+const FORMAT_KEY: &str = "NSStringLocalizedFormatKey";
+const SPEC_TYPE_KEY: &str = "NSStringFormatSpecTypeKey";
+const PLURAL_RULE_TYPE: &str = "NSStringPluralRuleType";
+const CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    let root = parse(content)?;
+
+    let mut tree = Map::new();
+    for (key, entry) in &root {
+        let Some(entry) = entry.as_dictionary() else {
+            continue;
+        };
+
+        let mut value = Map::new();
+        if let Some(format) = entry.get(FORMAT_KEY).and_then(Plist::as_string) {
+            value.insert(FORMAT_KEY.to_string(), json!(format));
+        }
+        for (name, variable) in plural_variables(entry) {
+            let forms: Map<String, Value> = variable
+                .iter()
+                .filter(|(category, _)| CATEGORIES.contains(&category.as_str()))
+                .filter_map(|(category, form)| Some((category.clone(), json!(form.as_string()?))))
+                .collect();
+            value.insert(name.clone(), Value::Object(forms));
+        }
+        tree.insert(key.clone(), Value::Object(value));
+    }
+
+    Ok(LoadedLocaleFile {
+        data: Value::Object(tree),
+        encoding,
+        warnings: Vec::new(),
+        context: BTreeMap::new(),
+    })
+}
+
+pub fn render(source: &str, tree: &Value, options: &RenderOptions) -> Result<String, CommandError> {
+    let mut root = parse(source)?;
+
+    for (key, entry) in root.iter_mut() {
+        let (Some(entry), Some(translation)) = (entry.as_dictionary_mut(), tree.get(key)) else {
+            continue;
+        };
+
+        if let Some(format) = translation.get(FORMAT_KEY).and_then(Value::as_str) {
+            entry.insert(FORMAT_KEY.to_string(), Plist::String(format.to_string()));
+        }
+        for (name, variable) in entry.iter_mut() {
+            let Some(forms) = translation.get(name).and_then(Value::as_object) else {
+                continue;
+            };
+            let Some(variable) = variable.as_dictionary_mut() else {
+                continue;
+            };
+            if !is_plural(variable) {
+                continue;
+            }
+            *variable = translate_variable(variable, forms, options.language);
+        }
+    }
+
+    let mut output = Vec::new();
+    Plist::Dictionary(root)
+        .to_writer_xml(&mut output)
+        .map_err(|e| CommandError::internal(format!("Failed to write stringsdict: {}", e)))?;
+    let mut output = String::from_utf8(output)
+        .map_err(|e| CommandError::internal(format!("Written stringsdict is not UTF-8: {}", e)))?;
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+fn parse(content: &str) -> Result<Dictionary, CommandError> {
+    Plist::from_reader_xml(content.as_bytes())
+        .map_err(|e| CommandError::invalid_input(format!("Invalid stringsdict file: {}", e)))?
+        .into_dictionary()
+        .ok_or_else(|| CommandError::invalid_input("A stringsdict file must contain a dictionary"))
+}
+
+fn is_plural(variable: &Dictionary) -> bool {
+    variable.get(SPEC_TYPE_KEY).and_then(Plist::as_string) == Some(PLURAL_RULE_TYPE)
+}
+
+fn plural_variables(entry: &Dictionary) -> impl Iterator<Item = (&String, &Dictionary)> {
+    entry.iter().filter_map(|(name, variable)| {
+        let variable = variable.as_dictionary()?;
+        is_plural(variable).then_some((name, variable))
+    })
+}
+
+// `variable` with its categories replaced by those of `language`, keeping the
+// other keys in place
+fn translate_variable(
+    variable: &Dictionary,
+    forms: &Map<String, Value>,
+    language: Option<&str>,
+) -> Dictionary {
+    let mut categories: Vec<String> = match language {
+        Some(language) => plurals::for_language(language)
+            .categories
+            .iter()
+            .map(|category| category.to_string())
+            .collect(),
+        None => forms.keys().cloned().collect(),
+    };
+    // Required by Foundation
+    if !categories.iter().any(|category| category == "other") {
+        categories.push("other".to_string());
+    }
+    categories.sort_by_key(|category| CATEGORIES.iter().position(|known| known == category));
+
+    let mut translated = Dictionary::new();
+    for (key, value) in variable {
+        if !CATEGORIES.contains(&key.as_str()) {
+            translated.insert(key.clone(), value.clone());
+        }
+    }
+    for category in categories {
+        let form = plurals::form(forms, &category);
+        translated.insert(category, Plist::String(form));
+    }
+    translated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>files_selected</key>
+	<dict>
+		<key>NSStringLocalizedFormatKey</key>
+		<string>%#@files@</string>
+		<key>files</key>
+		<dict>
+			<key>NSStringFormatSpecTypeKey</key>
+			<string>NSStringPluralRuleType</string>
+			<key>NSStringFormatValueTypeKey</key>
+			<string>d</string>
+			<key>one</key>
+			<string>%d file selected</string>
+			<key>other</key>
+			<string>%d files selected</string>
+		</dict>
+	</dict>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn plural_rules_round_trip_with_the_target_categories() {
+        let loaded = load(SOURCE, Encoding::default()).unwrap();
+        assert_eq!(
+            loaded.data,
+            json!({
+                "files_selected": {
+                    "NSStringLocalizedFormatKey": "%#@files@",
+                    "files": { "one": "%d file selected", "other": "%d files selected" },
+                },
+            })
+        );
+
+        let tree = json!({
+            "files_selected": {
+                "NSStringLocalizedFormatKey": "%#@files@",
+                "files": {
+                    "one": "Выбран %d файл",
+                    "few": "Выбрано %d файла",
+                    "many": "Выбрано %d файлов",
+                },
+            },
+        });
+        let options = RenderOptions {
+            language: Some("ru_ru"),
+            needs_review: false,
+        };
+        let rendered = render(SOURCE, &tree, &options).unwrap();
+
+        assert_eq!(
+            load(&rendered, Encoding::default()).unwrap().data,
+            json!({
+                "files_selected": {
+                    "NSStringLocalizedFormatKey": "%#@files@",
+                    "files": {
+                        "one": "Выбран %d файл",
+                        "few": "Выбрано %d файла",
+                        "many": "Выбрано %d файлов",
+                        "other": "Выбрано %d файлов",
+                    },
+                },
+            })
+        );
+        // The variable's type keys are kept
+        assert!(rendered.contains("<string>NSStringPluralRuleType</string>"));
+        assert!(rendered.contains("<key>NSStringFormatValueTypeKey</key>"));
+    }
+}
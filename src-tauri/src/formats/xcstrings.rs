@@ -0,0 +1,431 @@
+// Xcode String Catalogs (`.xcstrings`)
+//
+// A catalog is a JSON file holding every language of a target:
+//
+//   "strings" : {
+//     "Hello" : {
+//       "localizations" : {
+//         "en" : { "stringUnit" : { "state" : "translated", "value" : "Hello" } },
+//         "de" : { "stringUnit" : { "state" : "translated", "value" : "Hallo" } }
+//       }
+//     }
+//   }
+//
+// The tree holds the source language: a key's string unit, or an object of
+// CLDR categories for plural variations. Keys without a source localization
+// use the key itself, which is what Xcode extracts from the code. Keys marked
+// `shouldTranslate: false` are left out.
+//
+// Since there is only one file, translating writes the target language into
+// the catalog itself instead of creating a file per language. Only missing
+// localizations and those not yet `translated` (e.g. `new` or
+// `needs_review`) are filled in, so existing translations are kept. The
+// language is spelled the way the catalog already spells it (`de` for
+// `de_de`), or as a BCP 47 tag for a new language.
+use serde::Serialize;
+use serde_json::ser::Formatter;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::io;
+
+use super::{plurals, RenderOptions};
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::locale_json::LoadedLocaleFile;
+use crate::project::languages::{CodeStyle, LanguageMatcher};
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    let catalog = parse(content)?;
+    let source_language = source_language(&catalog)?;
+
+    let mut tree = Map::new();
+    for (key, entry) in strings(&catalog) {
+        if !should_translate(entry) {
+            continue;
+        }
+        let localization = entry
+            .get("localizations")
+            .and_then(|localizations| localizations.get(source_language));
+        let value = localization
+            .and_then(localization_value)
+            .unwrap_or_else(|| json!(key));
+        tree.insert(key.clone(), value);
+    }
+
+    Ok(LoadedLocaleFile {
+        data: Value::Object(tree),
+        encoding,
+        warnings: Vec::new(),
+        context: BTreeMap::new(),
+    })
+}
+
+pub fn render(source: &str, tree: &Value, options: &RenderOptions) -> Result<String, CommandError> {
+    let mut catalog = parse(source)?;
+    let language = options.language.ok_or_else(|| {
+        CommandError::invalid_input("The target language is needed to write a string catalog")
+    })?;
+    let language = catalog_language(&catalog, language);
+    let state = if options.needs_review {
+        "needs_review"
+    } else {
+        "translated"
+    };
+
+    let entries = catalog
+        .get_mut("strings")
+        .and_then(Value::as_object_mut)
+        .into_iter()
+        .flatten();
+    for (key, entry) in entries {
+        let Some(translation) = tree.get(key.as_str()) else {
+            continue;
+        };
+        if !should_translate(entry) {
+            continue;
+        }
+        let Some(entry) = entry.as_object_mut() else {
+            continue;
+        };
+
+        let localizations = entry.entry("localizations").or_insert_with(|| json!({}));
+        let Some(localizations) = localizations.as_object_mut() else {
+            continue;
+        };
+        if localizations.get(&language).is_some_and(is_translated) {
+            continue;
+        }
+
+        let localization = match translation {
+            Value::String(value) => string_unit(state, value),
+            Value::Object(forms) => {
+                let mut categories = plurals::for_language(&language).categories.to_vec();
+                // Required by Foundation
+                if !categories.contains(&"other") {
+                    categories.push("other");
+                }
+                let variations: Map<String, Value> = categories
+                    .into_iter()
+                    .map(|category| {
+                        let form = plurals::form(forms, category);
+                        (category.to_string(), string_unit(state, &form))
+                    })
+                    .collect();
+                json!({ "variations": { "plural": variations } })
+            }
+            _ => continue,
+        };
+        localizations.insert(language.clone(), localization);
+    }
+
+    let mut output = Vec::new();
+    let mut serializer =
+        serde_json::Serializer::with_formatter(&mut output, XcodeFormatter::default());
+    catalog
+        .serialize(&mut serializer)
+        .map_err(|e| CommandError::json("Failed to serialize string catalog", e))?;
+    let mut output = String::from_utf8(output)
+        .map_err(|e| CommandError::internal(format!("Written catalog is not UTF-8: {}", e)))?;
+    if source.ends_with('\n') {
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+fn parse(content: &str) -> Result<Value, CommandError> {
+    let catalog: Value = serde_json::from_str(content)
+        .map_err(|e| CommandError::json("Failed to parse string catalog", e))?;
+    if !catalog.is_object() {
+        return Err(CommandError::invalid_input(
+            "A string catalog must be a JSON object",
+        ));
+    }
+    Ok(catalog)
+}
+
+fn source_language(catalog: &Value) -> Result<&str, CommandError> {
+    catalog
+        .get("sourceLanguage")
+        .and_then(Value::as_str)
+        .ok_or_else(|| CommandError::invalid_input("The string catalog has no sourceLanguage"))
+}
+
+fn strings(catalog: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    catalog
+        .get("strings")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+}
+
+fn should_translate(entry: &Value) -> bool {
+    entry.get("shouldTranslate").and_then(Value::as_bool) != Some(false)
+}
+
+// The catalog's spelling of the app language code `language`
+fn catalog_language(catalog: &Value, language: &str) -> String {
+    let matcher = LanguageMatcher::new(&[language.to_string()]);
+    strings(catalog)
+        .filter_map(|(_, entry)| entry.get("localizations")?.as_object())
+        .flat_map(|localizations| localizations.keys())
+        .find(|existing| matcher.match_name(existing).is_some())
+        .cloned()
+        .unwrap_or_else(|| CodeStyle::Hyphen.format(language))
+}
+
+// The text of a localization: its string unit, or its plural forms
+fn localization_value(localization: &Value) -> Option<Value> {
+    if let Some(value) = localization.pointer("/stringUnit/value") {
+        return Some(value.clone());
+    }
+    let plural = localization.pointer("/variations/plural")?.as_object()?;
+    let forms: Map<String, Value> = plural
+        .iter()
+        .filter_map(|(category, variation)| {
+            let value = variation.pointer("/stringUnit/value")?;
+            Some((category.clone(), value.clone()))
+        })
+        .collect();
+    Some(Value::Object(forms))
+}
+
+// Whether every string unit of a localization is translated
+fn is_translated(localization: &Value) -> bool {
+    let mut states = Vec::new();
+    collect_states(localization, &mut states);
+    !states.is_empty() && states.iter().all(|state| *state == "translated")
+}
+
+fn collect_states<'a>(value: &'a Value, states: &mut Vec<&'a str>) {
+    if let Some(state) = value.pointer("/stringUnit/state").and_then(Value::as_str) {
+        states.push(state);
+    }
+    if let Some(variations) = value.get("variations").and_then(Value::as_object) {
+        for variation in variations.values().filter_map(Value::as_object) {
+            for value in variation.values() {
+                collect_states(value, states);
+            }
+        }
+    }
+}
+
+fn string_unit(state: &str, value: &str) -> Value {
+    json!({ "stringUnit": { "state": state, "value": value } })
+}
+
+// Pretty printing the way Xcode writes catalogs: two spaces of indentation
+// and a space on both sides of the colon
+#[derive(Default)]
+struct XcodeFormatter {
+    indent: usize,
+    has_value: bool,
+}
+
+impl XcodeFormatter {
+    fn newline<W: ?Sized + io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b"\n")?;
+        for _ in 0..self.indent {
+            writer.write_all(b"  ")?;
+        }
+        Ok(())
+    }
+}
+
+impl Formatter for XcodeFormatter {
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.indent += 1;
+        self.has_value = false;
+        writer.write_all(b"[")
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.indent -= 1;
+        if self.has_value {
+            self.newline(writer)?;
+        }
+        writer.write_all(b"]")
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if !first {
+            writer.write_all(b",")?;
+        }
+        self.newline(writer)
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, _writer: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.indent += 1;
+        self.has_value = false;
+        writer.write_all(b"{")
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.indent -= 1;
+        if self.has_value {
+            self.newline(writer)?;
+        }
+        writer.write_all(b"}")
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if !first {
+            writer.write_all(b",")?;
+        }
+        self.newline(writer)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b" : ")
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, _writer: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{
+  "sourceLanguage" : "en",
+  "strings" : {
+    "Hello" : {
+      "localizations" : {
+        "de" : {
+          "stringUnit" : {
+            "state" : "translated",
+            "value" : "Hallo"
+          }
+        },
+        "fr" : {
+          "stringUnit" : {
+            "state" : "new",
+            "value" : ""
+          }
+        }
+      }
+    },
+    "Version" : {
+      "shouldTranslate" : false
+    },
+    "items" : {
+      "localizations" : {
+        "en" : {
+          "variations" : {
+            "plural" : {
+              "one" : {
+                "stringUnit" : {
+                  "state" : "translated",
+                  "value" : "%lld item"
+                }
+              },
+              "other" : {
+                "stringUnit" : {
+                  "state" : "translated",
+                  "value" : "%lld items"
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  },
+  "version" : "1.0"
+}
+"#;
+
+    fn options(language: &str) -> RenderOptions<'_> {
+        RenderOptions {
+            language: Some(language),
+            needs_review: false,
+        }
+    }
+
+    #[test]
+    fn the_tree_holds_the_source_language() {
+        let loaded = load(SOURCE, Encoding::default()).unwrap();
+
+        assert_eq!(
+            loaded.data,
+            json!({
+                "Hello": "Hello",
+                "items": { "one": "%lld item", "other": "%lld items" },
+            })
+        );
+
+        // Nothing to fill in writes the catalog back the way Xcode wrote it
+        assert_eq!(
+            render(SOURCE, &json!({}), &options("de_de")).unwrap(),
+            SOURCE
+        );
+    }
+
+    #[test]
+    fn rendering_fills_in_only_missing_or_unfinished_localizations() {
+        let tree = json!({
+            "Hello": "Bonjour",
+            "items": { "one": "%lld élément", "many": "%lld d'éléments", "other": "%lld éléments" },
+        });
+        let rendered = render(SOURCE, &tree, &options("fr_fr")).unwrap();
+        let catalog: Value = serde_json::from_str(&rendered).unwrap();
+
+        // `fr` was new, so it is filled in under the catalog's spelling
+        assert_eq!(
+            catalog.pointer("/strings/Hello/localizations/fr/stringUnit"),
+            Some(&json!({ "state": "translated", "value": "Bonjour" }))
+        );
+        assert_eq!(
+            catalog
+                .pointer("/strings/items/localizations/fr/variations/plural/one/stringUnit/value"),
+            Some(&json!("%lld élément"))
+        );
+        // Other languages are kept as they were
+        assert_eq!(
+            catalog.pointer("/strings/Hello/localizations/de/stringUnit/value"),
+            Some(&json!("Hallo"))
+        );
+        assert!(catalog.pointer("/strings/Version/localizations").is_none());
+        assert!(rendered.contains("\"sourceLanguage\" : \"en\""));
+        assert!(rendered.ends_with("}\n"));
+    }
+
+    #[test]
+    fn rendering_adds_a_missing_language_and_keeps_translations() {
+        let tree = json!({ "Hello": "Hej", "items": { "one": "%lld sak", "other": "%lld saker" } });
+        let rendered = render(SOURCE, &tree, &options("sv_se")).unwrap();
+        let catalog: Value = serde_json::from_str(&rendered).unwrap();
+
+        assert_eq!(
+            catalog.pointer("/strings/Hello/localizations/sv-SE/stringUnit"),
+            Some(&json!({ "state": "translated", "value": "Hej" }))
+        );
+        assert_eq!(
+            catalog.pointer("/strings/Hello/localizations/de/stringUnit/value"),
+            Some(&json!("Hallo"))
+        );
+
+        // A translated localization isn't overwritten
+        let rendered = render(&rendered, &json!({ "Hello": "Tjena" }), &options("sv_se")).unwrap();
+        let catalog: Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(
+            catalog.pointer("/strings/Hello/localizations/sv-SE/stringUnit/value"),
+            Some(&json!("Hej"))
+        );
+    }
+}
@@ -0,0 +1,716 @@
+// XLIFF 1.2 and 2.0 exchange files
+//
+// Every translatable segment becomes a key: `<trans-unit>` in 1.2, the
+// `<segment>`s of a `<unit>` in 2.0. Units are keyed by their `resname`
+// (1.2) or `name` (2.0), falling back to `id`. A 2.0 unit with several
+// segments becomes an object keyed by segment id (or position). The
+// `<note>`s of a unit are passed to the translator as context for its key.
+//
+// Inline codes in a source (`<x/>`, `<ph>`, `<g>`, `<pc>`, ...) become
+// `{{phN}}` tokens, with `{{/phN}}` closing the paired ones, so they survive
+// translation:
+//
+//   <source>Hello <ph id="1">%s</ph>, see <g id="2">this</g></source>
+//   "Hello {{ph0}}, see {{ph1}}this{{/ph1}}"
+//
+// A translated file is the source file with a `<target>` written after each
+// translated source, replacing any existing one, and the target language set
+// on `<file>` (1.2) or `<xliff>` (2.0). Everything else, notes included, is
+// copied as is. Targets are marked `state="translated"`, or for review
+// (`needs-review-translation` in 1.2, a `localekit:needs-review` subState in
+// 2.0) when the run reported a problem or the tokens in a translation don't
+// match its source.
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use super::xml::{attribute, is_whitespace, syntax_error, write};
+use super::RenderOptions;
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+use crate::locale_json::{LoadWarning, LoadedLocaleFile};
+use crate::project::languages::CodeStyle;
+
+const REVIEW_SUBSTATE: &str = "localekit:needs-review";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Version {
+    #[default]
+    V1,
+    V2,
+}
+
+// An inline code in a source, by token index
+#[derive(Debug, Clone)]
+enum Inline {
+    // `<x/>`, `<ph>...</ph>`: the element with its native code content
+    Standalone(Vec<Event<'static>>),
+    // `<g>`, `<pc>`, `<mrk>`: wraps translatable text
+    Paired(Event<'static>, Option<Event<'static>>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Content {
+    text: String,
+    inlines: Vec<Inline>,
+}
+
+#[derive(Debug)]
+struct Segment {
+    unit: String,
+    // Position within the unit
+    index: usize,
+    id: Option<String>,
+    source: Content,
+}
+
+#[derive(Debug, Default)]
+struct Document {
+    version: Version,
+    segments: Vec<Segment>,
+    // Text of the `<note>`s in each unit, by unit key
+    notes: BTreeMap<String, Vec<String>>,
+}
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    let document = Document::parse(content)?;
+
+    let mut tree = Map::new();
+    let mut duplicates = BTreeMap::new();
+    let counts = document.segment_counts();
+    for segment in &document.segments {
+        let text = json!(segment.source.text);
+        let duplicate = if counts[segment.unit.as_str()] == 1 {
+            tree.insert(segment.unit.clone(), text).is_some()
+        } else {
+            let unit = tree
+                .entry(segment.unit.clone())
+                .or_insert_with(|| json!({}));
+            if !unit.is_object() {
+                *unit = json!({});
+            }
+            unit.as_object_mut()
+                .is_some_and(|segments| segments.insert(segment.key(), text).is_some())
+        };
+        if duplicate {
+            *duplicates.entry(segment.unit.clone()).or_insert(1) += 1;
+        }
+    }
+
+    let warnings = duplicates
+        .into_iter()
+        .map(|(path, occurrences)| LoadWarning::DuplicateKey { path, occurrences })
+        .collect();
+    let context = document
+        .notes
+        .into_iter()
+        .map(|(unit, notes)| (unit, notes.join("; ")))
+        .collect();
+    Ok(LoadedLocaleFile {
+        data: Value::Object(tree),
+        encoding,
+        warnings,
+        context,
+    })
+}
+
+pub fn render(source: &str, tree: &Value, options: &RenderOptions) -> Result<String, CommandError> {
+    let document = Document::parse(source)?;
+    let counts = document.segment_counts();
+    let segments: HashMap<(&str, usize), &Segment> = document
+        .segments
+        .iter()
+        .map(|segment| ((segment.unit.as_str(), segment.index), segment))
+        .collect();
+
+    // The target for segment `index` of `unit`, if the tree translates it
+    let plan = |unit: &str, index: usize| -> Option<PlannedTarget> {
+        let segment = segments.get(&(unit, index))?;
+        let value = tree.get(unit)?;
+        let text = if counts.get(unit).copied().unwrap_or(0) > 1 {
+            value.get(segment.key())?.as_str()?
+        } else {
+            value.as_str()?
+        };
+        let (events, tokens_match) = target_events(text, &segment.source);
+        Some(PlannedTarget {
+            events,
+            needs_review: options.needs_review || !tokens_match,
+        })
+    };
+
+    let mut reader = Reader::from_str(source);
+    let mut writer = Writer::new(Vec::new());
+    let mut state = RenderState::default();
+    let target_language = options
+        .language
+        .map(|language| CodeStyle::Hyphen.format(language));
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| syntax_error("XLIFF file", source, reader.buffer_position(), e))?;
+
+        // Drop the target being replaced
+        if state.skip_depth > 0 {
+            match event {
+                Event::Start(_) => state.skip_depth += 1,
+                Event::End(_) => state.skip_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+
+        // Hold whitespace back until it's clear it isn't in front of a
+        // replaced target
+        if is_whitespace(&event) {
+            if let Some(pending) = state.whitespace.replace(event.into_owned()) {
+                write(&mut writer, pending)?;
+            }
+            continue;
+        }
+
+        let mut event = event.into_owned();
+        let mut after: Vec<Event<'static>> = Vec::new();
+        let mut updated: Option<BytesStart<'static>> = None;
+        match &event {
+            Event::Start(start) | Event::Empty(start) => {
+                let is_start = matches!(event, Event::Start(_));
+                match start.local_name().as_ref() {
+                    b"xliff" if document.version == Version::V2 => {
+                        updated = target_language
+                            .as_deref()
+                            .map(|language| with_attributes(start, &[("trgLang", language)], &[]));
+                    }
+                    b"file" if document.version == Version::V1 => {
+                        updated = target_language.as_deref().map(|language| {
+                            with_attributes(start, &[("target-language", language)], &[])
+                        });
+                    }
+                    b"alt-trans" | b"seg-source" | b"ignorable" if is_start => state.excluded += 1,
+                    b"trans-unit" if document.version == Version::V1 => {
+                        let unit = unit_key(start, b"resname")?;
+                        state.target = plan(&unit, 0);
+                        state.unit = Some(unit);
+                    }
+                    b"unit" if document.version == Version::V2 => {
+                        state.unit = Some(unit_key(start, b"name")?);
+                        state.next_segment = 0;
+                    }
+                    b"segment" if document.version == Version::V2 => {
+                        let index = state.next_segment;
+                        state.next_segment += 1;
+                        state.target = state.unit.as_deref().and_then(|unit| plan(unit, index));
+                        if let Some(target) = &state.target {
+                            let mut set = vec![("state", "translated")];
+                            if target.needs_review {
+                                set.push(("subState", REVIEW_SUBSTATE));
+                            }
+                            updated = Some(with_attributes(start, &set, &["subState"]));
+                        }
+                    }
+                    b"source" if state.excluded == 0 && state.target.is_some() => {
+                        state.indent = state.whitespace.clone();
+                        if !is_start {
+                            after = state.take_target(document.version);
+                        }
+                    }
+                    b"target" if state.excluded == 0 && state.replaced => {
+                        // Replaced by the target written after the source
+                        state.whitespace = None;
+                        if is_start {
+                            state.skip_depth = 1;
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(end) => match end.local_name().as_ref() {
+                b"alt-trans" | b"seg-source" | b"ignorable" => {
+                    state.excluded = state.excluded.saturating_sub(1)
+                }
+                b"source" if state.excluded == 0 && state.target.is_some() => {
+                    after = state.take_target(document.version);
+                }
+                b"trans-unit" | b"segment" => {
+                    state.target = None;
+                    state.replaced = false;
+                }
+                b"unit" => state.unit = None,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        if let Some(updated) = updated {
+            event = match event {
+                Event::Empty(_) => Event::Empty(updated),
+                _ => Event::Start(updated),
+            };
+        }
+
+        if let Some(pending) = state.whitespace.take() {
+            write(&mut writer, pending)?;
+        }
+        write(&mut writer, event)?;
+        for event in after {
+            write(&mut writer, event)?;
+        }
+    }
+    if let Some(pending) = state.whitespace.take() {
+        write(&mut writer, pending)?;
+    }
+
+    String::from_utf8(writer.into_inner())
+        .map_err(|e| CommandError::internal(format!("Written XLIFF is not UTF-8: {}", e)))
+}
+
+struct PlannedTarget {
+    // Content of the `<target>` element
+    events: Vec<Event<'static>>,
+    needs_review: bool,
+}
+
+#[derive(Default)]
+struct RenderState {
+    unit: Option<String>,
+    // 2.0: index of the next segment in the unit
+    next_segment: usize,
+    // Target for the current segment, until it is written
+    target: Option<PlannedTarget>,
+    // Whether the current segment's target was written, so an existing one
+    // is dropped
+    replaced: bool,
+    // Depth inside elements whose sources aren't translated (`alt-trans`...)
+    excluded: usize,
+    // Depth inside a dropped target
+    skip_depth: usize,
+    whitespace: Option<Event<'static>>,
+    // Whitespace in front of the current source, repeated for the target
+    indent: Option<Event<'static>>,
+}
+
+impl RenderState {
+    // The events for the current segment's `<target>`, to follow its source
+    fn take_target(&mut self, version: Version) -> Vec<Event<'static>> {
+        let Some(target) = self.target.take() else {
+            return Vec::new();
+        };
+        self.replaced = true;
+
+        let mut start = BytesStart::new("target");
+        if version == Version::V1 {
+            let state = if target.needs_review {
+                "needs-review-translation"
+            } else {
+                "translated"
+            };
+            start.push_attribute(("state", state));
+        }
+
+        let mut events = Vec::new();
+        if let Some(indent) = &self.indent {
+            events.push(indent.clone());
+        }
+        events.push(Event::Start(start));
+        events.extend(target.events);
+        events.push(Event::End(BytesEnd::new("target")));
+        events
+    }
+}
+
+impl Segment {
+    // Key within a unit that has several segments
+    fn key(&self) -> String {
+        self.id.clone().unwrap_or_else(|| self.index.to_string())
+    }
+}
+
+impl Document {
+    fn parse(content: &str) -> Result<Self, CommandError> {
+        let mut reader = Reader::from_str(content);
+        let mut document = Document::default();
+        let mut unit: Option<String> = None;
+        let mut segment_id: Option<String> = None;
+        let mut next_index = 0;
+        let mut excluded = 0usize;
+        let mut capture: Option<Capture> = None;
+        // Text of the `<note>` being read
+        let mut note: Option<String> = None;
+
+        loop {
+            let event = reader
+                .read_event()
+                .map_err(|e| syntax_error("XLIFF file", content, reader.buffer_position(), e))?;
+
+            if let Some(current) = capture.as_mut() {
+                if current.push(event)? {
+                    let source = capture
+                        .take()
+                        .map(|capture| capture.content)
+                        .unwrap_or_default();
+                    document.segments.push(Segment {
+                        unit: unit.clone().unwrap_or_default(),
+                        index: next_index,
+                        id: segment_id.clone(),
+                        source,
+                    });
+                    next_index += 1;
+                }
+                continue;
+            }
+
+            if let Some(text) = note.as_mut() {
+                match &event {
+                    Event::Text(content) => text.push_str(&content.unescape().map_err(|e| {
+                        CommandError::invalid_input(format!("Invalid XLIFF text: {}", e))
+                    })?),
+                    Event::CData(data) => text.push_str(&String::from_utf8_lossy(data)),
+                    Event::End(end) if end.local_name().as_ref() == b"note" => {
+                        // Notes are often wrapped to fit the file; the
+                        // translator gets them on one line
+                        let text = note.take().unwrap_or_default();
+                        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                        if let Some(unit) = unit.as_ref().filter(|_| !text.is_empty()) {
+                            document.notes.entry(unit.clone()).or_default().push(text);
+                        }
+                    }
+                    Event::Eof => break,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match &event {
+                Event::Start(start) | Event::Empty(start) => {
+                    let is_start = matches!(event, Event::Start(_));
+                    match start.local_name().as_ref() {
+                        b"xliff" => {
+                            let version = attribute(start, b"version")?.unwrap_or_default();
+                            if version.starts_with('2') {
+                                document.version = Version::V2;
+                            }
+                        }
+                        b"alt-trans" | b"seg-source" | b"ignorable" if is_start => excluded += 1,
+                        b"note" if is_start && excluded == 0 && unit.is_some() => {
+                            note = Some(String::new());
+                        }
+                        b"trans-unit" if document.version == Version::V1 => {
+                            unit = Some(unit_key(start, b"resname")?);
+                            segment_id = None;
+                            next_index = 0;
+                        }
+                        b"unit" if document.version == Version::V2 => {
+                            unit = Some(unit_key(start, b"name")?);
+                            next_index = 0;
+                        }
+                        b"segment" if document.version == Version::V2 => {
+                            segment_id = attribute(start, b"id")?;
+                        }
+                        b"source" if excluded == 0 && unit.is_some() => {
+                            if is_start {
+                                capture = Some(Capture::default());
+                            } else {
+                                document.segments.push(Segment {
+                                    unit: unit.clone().unwrap_or_default(),
+                                    index: next_index,
+                                    id: segment_id.clone(),
+                                    source: Content::default(),
+                                });
+                                next_index += 1;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Event::End(end) => match end.local_name().as_ref() {
+                    b"alt-trans" | b"seg-source" | b"ignorable" => {
+                        excluded = excluded.saturating_sub(1)
+                    }
+                    b"trans-unit" | b"unit" => unit = None,
+                    b"segment" => segment_id = None,
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        Ok(document)
+    }
+
+    // Number of segments per unit
+    fn segment_counts(&self) -> HashMap<&str, usize> {
+        let mut counts = HashMap::new();
+        for segment in &self.segments {
+            *counts.entry(segment.unit.as_str()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+// Collects the content of a `<source>`
+#[derive(Default)]
+struct Capture {
+    content: Content,
+    // Paired inline codes currently open, by token index
+    open: Vec<usize>,
+    // Standalone inline code being read, with its nesting depth
+    standalone: Option<(Vec<Event<'static>>, usize)>,
+}
+
+impl Capture {
+    // Returns true once the source has ended
+    fn push(&mut self, event: Event) -> Result<bool, CommandError> {
+        if let Some((events, depth)) = self.standalone.as_mut() {
+            match &event {
+                Event::Start(_) => *depth += 1,
+                Event::End(_) => *depth -= 1,
+                _ => {}
+            }
+            events.push(event.into_owned());
+            if *depth == 0 {
+                if let Some((events, _)) = self.standalone.take() {
+                    self.add_token(Inline::Standalone(events));
+                }
+            }
+            return Ok(false);
+        }
+
+        match event {
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| {
+                    CommandError::invalid_input(format!("Invalid XLIFF text: {}", e))
+                })?;
+                self.content.text.push_str(&text);
+            }
+            Event::CData(data) => self.content.text.push_str(&String::from_utf8_lossy(&data)),
+            Event::Empty(_) => self.add_token(Inline::Standalone(vec![event.into_owned()])),
+            Event::Start(start) => {
+                if matches!(start.local_name().as_ref(), b"g" | b"pc" | b"mrk") {
+                    let index = self.content.inlines.len();
+                    self.add_token(Inline::Paired(Event::Start(start.into_owned()), None));
+                    self.open.push(index);
+                } else {
+                    self.standalone = Some((vec![Event::Start(start.into_owned())], 1));
+                }
+            }
+            Event::End(end) => match self.open.pop() {
+                Some(index) => {
+                    if let Some(Inline::Paired(_, close)) = self.content.inlines.get_mut(index) {
+                        *close = Some(Event::End(end.into_owned()));
+                    }
+                    self.content.text.push_str(&token(index, true));
+                }
+                // The end of the source itself
+                None => return Ok(true),
+            },
+            Event::Eof => {
+                return Err(CommandError::invalid_input(
+                    "Unclosed <source> in XLIFF file",
+                ))
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn add_token(&mut self, inline: Inline) {
+        let index = self.content.inlines.len();
+        self.content.inlines.push(inline);
+        self.content.text.push_str(&token(index, false));
+    }
+}
+
+fn token(index: usize, closing: bool) -> String {
+    if closing {
+        format!("{{{{/ph{}}}}}", index)
+    } else {
+        format!("{{{{ph{}}}}}", index)
+    }
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Token(usize, bool),
+}
+
+// The events for a translated text, with its tokens turned back into the
+// source's inline codes. Also returns whether the tokens match the source's.
+// Paired codes that end up unbalanced are dropped, so the file stays valid.
+fn target_events(text: &str, source: &Content) -> (Vec<Event<'static>>, bool) {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let parsed = rest[start + 2..].find("}}").and_then(|end| {
+            let inner = &rest[start + 2..start + 2 + end];
+            let (closing, name) = match inner.strip_prefix('/') {
+                Some(name) => (true, name),
+                None => (false, inner),
+            };
+            let index: usize = name.strip_prefix("ph")?.parse().ok()?;
+            let valid = match source.inlines.get(index)? {
+                Inline::Standalone(_) => !closing,
+                Inline::Paired(..) => true,
+            };
+            valid.then_some((index, closing, start + 4 + inner.len()))
+        });
+        match parsed {
+            Some((index, closing, consumed)) => {
+                pieces.push(Piece::Text(&rest[..start]));
+                pieces.push(Piece::Token(index, closing));
+                rest = &rest[consumed..];
+            }
+            None => {
+                pieces.push(Piece::Text(&rest[..start + 2]));
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+    pieces.push(Piece::Text(rest));
+
+    let mut used: Vec<(usize, bool)> = pieces
+        .iter()
+        .filter_map(|piece| match piece {
+            Piece::Token(index, closing) => Some((*index, *closing)),
+            Piece::Text(_) => None,
+        })
+        .collect();
+    let mut expected: Vec<(usize, bool)> = source
+        .inlines
+        .iter()
+        .enumerate()
+        .flat_map(|(index, inline)| match inline {
+            Inline::Standalone(_) => vec![(index, false)],
+            Inline::Paired(..) => vec![(index, false), (index, true)],
+        })
+        .collect();
+    let paired: Vec<(usize, bool)> = used
+        .iter()
+        .copied()
+        .filter(|(index, _)| matches!(source.inlines[*index], Inline::Paired(..)))
+        .collect();
+    let balanced = is_balanced(&paired);
+    used.sort_unstable();
+    expected.sort_unstable();
+    let tokens_match = balanced && used == expected;
+
+    let mut events = Vec::new();
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) if !text.is_empty() => {
+                events.push(Event::Text(BytesText::new(text).into_owned()))
+            }
+            Piece::Text(_) => {}
+            Piece::Token(index, closing) => match &source.inlines[index] {
+                Inline::Standalone(inline) => events.extend(inline.iter().cloned()),
+                Inline::Paired(open, close) if balanced => {
+                    let event = if closing {
+                        close.clone()
+                    } else {
+                        Some(open.clone())
+                    };
+                    events.extend(event);
+                }
+                Inline::Paired(..) => {}
+            },
+        }
+    }
+
+    (events, tokens_match)
+}
+
+// Whether every opened token is closed, in order
+fn is_balanced(tokens: &[(usize, bool)]) -> bool {
+    let mut open = Vec::new();
+    for &(index, closing) in tokens {
+        if !closing {
+            open.push(index);
+        } else if open.pop() != Some(index) {
+            return false;
+        }
+    }
+    open.is_empty()
+}
+
+fn unit_key(start: &BytesStart, name_attribute: &[u8]) -> Result<String, CommandError> {
+    match attribute(start, name_attribute)? {
+        Some(name) if !name.is_empty() => Ok(name),
+        _ => attribute(start, b"id")?
+            .ok_or_else(|| CommandError::invalid_input("XLIFF unit without an id")),
+    }
+}
+
+// A copy of `start` with the `set` attributes replaced or added and the
+// `remove` attributes dropped
+fn with_attributes(
+    start: &BytesStart,
+    set: &[(&str, &str)],
+    remove: &[&str],
+) -> BytesStart<'static> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+    let mut updated = BytesStart::new(name);
+    for attribute in start.attributes().flatten() {
+        let key = attribute.key.as_ref();
+        let replaced = set.iter().any(|(name, _)| name.as_bytes() == key)
+            || remove.iter().any(|name| name.as_bytes() == key);
+        if !replaced {
+            updated.push_attribute(attribute);
+        }
+    }
+    for &(name, value) in set {
+        updated.push_attribute((name, value));
+    }
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_become_context() {
+        let v1 = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2">
+  <file source-language="en" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="1" resname="open">
+        <source>Open</source>
+        <note>Button that opens
+          a saved document</note>
+        <note from="developer">Keep it short</note>
+      </trans-unit>
+      <trans-unit id="2" resname="close">
+        <source>Close</source>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>"#;
+        let loaded = load(v1, Encoding::default()).unwrap();
+        assert_eq!(loaded.data, json!({ "open": "Open", "close": "Close" }));
+        assert_eq!(
+            loaded.context.get("open").map(String::as_str),
+            Some("Button that opens a saved document; Keep it short")
+        );
+        assert!(!loaded.context.contains_key("close"));
+
+        let v2 = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en">
+  <file id="f1">
+    <notes><note>Applies to the whole file</note></notes>
+    <unit id="u1" name="greeting">
+      <notes><note category="description">Shown on the start screen</note></notes>
+      <segment><source>Hello</source></segment>
+    </unit>
+  </file>
+</xliff>"#;
+        let loaded = load(v2, Encoding::default()).unwrap();
+        assert_eq!(loaded.data, json!({ "greeting": "Hello" }));
+        assert_eq!(loaded.context.len(), 1);
+        assert_eq!(
+            loaded.context.get("greeting").map(String::as_str),
+            Some("Shown on the start screen")
+        );
+    }
+}
@@ -0,0 +1,44 @@
+// Helpers shared by the XML formats (XLIFF, Android resources)
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Writer;
+use serde_json::json;
+
+use crate::error::CommandError;
+
+pub fn attribute(start: &BytesStart, name: &[u8]) -> Result<Option<String>, CommandError> {
+    let attribute = start
+        .try_get_attribute(name)
+        .map_err(|e| CommandError::invalid_input(format!("Invalid XML attribute: {}", e)))?;
+    attribute
+        .map(|attribute| {
+            attribute
+                .unescape_value()
+                .map(|value| value.into_owned())
+                .map_err(|e| CommandError::invalid_input(format!("Invalid XML attribute: {}", e)))
+        })
+        .transpose()
+}
+
+// Text between elements that is only indentation
+pub fn is_whitespace(event: &Event) -> bool {
+    matches!(event, Event::Text(text) if text.iter().all(u8::is_ascii_whitespace))
+}
+
+pub fn write(writer: &mut Writer<Vec<u8>>, event: Event) -> Result<(), CommandError> {
+    writer
+        .write_event(event)
+        .map_err(|e| CommandError::internal(format!("Failed to write XML: {}", e)))
+}
+
+// `kind` names the file, e.g. "XLIFF file"
+pub fn syntax_error(
+    kind: &str,
+    content: &str,
+    position: usize,
+    error: quick_xml::Error,
+) -> CommandError {
+    let before = &content.as_bytes()[..position.min(content.len())];
+    let line = before.iter().filter(|&&byte| byte == b'\n').count() + 1;
+    CommandError::invalid_input(format!("Invalid {} at line {}: {}", kind, line, error))
+        .with_details(json!({ "line": line }))
+}
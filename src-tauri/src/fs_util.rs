@@ -0,0 +1,252 @@
+// Crash-safe file writing helpers
+//
+// Writes go to a temporary file in the destination directory, are flushed to
+// disk, and then renamed over the target, so readers only ever see the old or
+// the new contents. On Unix the directory is fsynced afterwards so the rename
+// itself survives a power loss, and private files are created 0600 (0700 for
+// directories) instead of inheriting the umask.
+//
+// `now_millis` stamps the records the app keeps in its own JSON files (key
+// metadata, scope grants, recent files).
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileMode {
+    // Whatever the umask gives a newly created file
+    Default,
+    // Readable and writable by the owner only (0600 on Unix)
+    Private,
+}
+
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Replace `path` with `contents` atomically
+pub fn write_atomic(path: &Path, contents: &[u8], mode: FileMode) -> io::Result<()> {
+    let temp_path = write_temp(path, contents, mode)?;
+
+    if let Err(e) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    sync_parent_dir(path)
+}
+
+// Create `path` with `contents` atomically, failing with `AlreadyExists`
+// instead of replacing a file that is already there
+pub fn create_atomic(path: &Path, contents: &[u8], mode: FileMode) -> io::Result<()> {
+    let temp_path = write_temp(path, contents, mode)?;
+
+    // A hard link fails if the target exists, unlike rename
+    let linked = fs::hard_link(&temp_path, path);
+    let _ = fs::remove_file(&temp_path);
+    linked?;
+
+    sync_parent_dir(path)
+}
+
+// Create a directory (and its parents) readable by the owner only. An
+// existing directory has its permissions tightened.
+pub fn create_private_dir(path: &Path) -> io::Result<()> {
+    fs::create_dir_all(path)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    }
+
+    Ok(())
+}
+
+fn write_temp(path: &Path, contents: &[u8], mode: FileMode) -> io::Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name"))?;
+
+    let temp_path = path.with_file_name(format!(
+        ".{}.tmp-{}-{}",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if mode == FileMode::Private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    let result = options.open(&temp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+
+    Ok(temp_path)
+}
+
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        fs::File::open(parent)?.sync_all()?;
+    }
+
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+// Milliseconds since the Unix epoch, 0 if the clock is before it
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Empty directory for a test, unique to the test process
+#[cfg(test)]
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("localekit-test-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("failed to create test directory");
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leftover_temp_files(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().contains(".tmp-"))
+            .collect()
+    }
+
+    #[cfg(unix)]
+    fn mode_of(path: &Path) -> u32 {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    // The umask is process-wide, so the tests that depend on it share it
+    #[cfg(unix)]
+    static UMASK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[cfg(unix)]
+    fn with_umask<T>(umask: libc::mode_t, f: impl FnOnce() -> T) -> T {
+        let _guard = UMASK.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = unsafe { libc::umask(umask) };
+        let result = f();
+        unsafe { libc::umask(previous) };
+        result
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_files_are_owner_only() {
+        let dir = test_dir("fs-private-file");
+        let path = dir.join("secret.dat");
+
+        with_umask(0o022, || {
+            write_atomic(&path, b"one", FileMode::Private).unwrap();
+            assert_eq!(mode_of(&path), 0o600);
+
+            // Replacing the file keeps it private
+            write_atomic(&path, b"two", FileMode::Private).unwrap();
+            assert_eq!(mode_of(&path), 0o600);
+
+            let created = dir.join("created.dat");
+            create_atomic(&created, b"one", FileMode::Private).unwrap();
+            assert_eq!(mode_of(&created), 0o600);
+        });
+        assert_eq!(fs::read(&path).unwrap(), b"two");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn default_files_follow_the_umask() {
+        let dir = test_dir("fs-default-file");
+        let path = dir.join("en.json");
+
+        with_umask(0o022, || {
+            write_atomic(&path, b"{}", FileMode::Default).unwrap();
+        });
+        assert_eq!(mode_of(&path), 0o644);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_dirs_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("fs-private-dir");
+        let keys_dir = dir.join("nested").join(".keys");
+
+        with_umask(0o022, || {
+            create_private_dir(&keys_dir).unwrap();
+            assert_eq!(mode_of(&keys_dir), 0o700);
+
+            // An existing, wider directory is tightened
+            fs::set_permissions(&keys_dir, fs::Permissions::from_mode(0o755)).unwrap();
+            create_private_dir(&keys_dir).unwrap();
+            assert_eq!(mode_of(&keys_dir), 0o700);
+        });
+    }
+
+    #[test]
+    fn failed_replace_leaves_the_target_alone() {
+        let dir = test_dir("fs-failed-replace");
+        // A file can't be renamed over a non-empty directory
+        let target = dir.join("en.json");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("keep.txt"), b"keep").unwrap();
+
+        assert!(write_atomic(&target, b"{}", FileMode::Private).is_err());
+        assert_eq!(fs::read(target.join("keep.txt")).unwrap(), b"keep");
+        assert!(leftover_temp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn failed_create_leaves_the_original_file_alone() {
+        let dir = test_dir("fs-failed-create");
+        let path = dir.join("data.key");
+        fs::write(&path, b"original").unwrap();
+
+        let error = create_atomic(&path, b"replacement", FileMode::Private).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert!(leftover_temp_files(&dir).is_empty());
+    }
+
+    #[test]
+    fn missing_parent_directory_fails_without_temp_files() {
+        let dir = test_dir("fs-missing-parent");
+        let path = dir.join("missing").join("en.json");
+
+        assert!(write_atomic(&path, b"{}", FileMode::Default).is_err());
+        assert!(!path.exists());
+        assert!(leftover_temp_files(&dir).is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+// Formatting style of a JSON file, so generated files match their source
+//
+// The frontend produces `JSON.stringify(value, null, 2)` output. Before it is
+// written, write_json_file parses it and re-renders it in the style detected
+// from a reference file (the source locale, or the file being replaced):
+// indentation, line endings, trailing newline and whether non-ASCII
+// characters are written raw or as `\uXXXX` escapes. Key order is kept as is.
+// A project config can pin any of these instead (see `StyleOverrides`).
+use serde::{Deserialize, Serialize};
+use serde_json::ser::{CompactFormatter, PrettyFormatter, Serializer};
+use serde_json::Value;
+
+use crate::error::CommandError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "width")]
+pub enum Indent {
+    Spaces(usize),
+    Tabs,
+    // Everything on one line
+    Compact,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonStyle {
+    pub indent: Indent,
+    pub line_ending: LineEnding,
+    pub trailing_newline: bool,
+    pub escape_unicode: bool,
+}
+
+// Formatting rules that win over the detected style. `indent` is a number of
+// spaces, "tab" or "compact". Read from config files in snake_case, with
+// camelCase accepted too.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "snake_case"))]
+pub struct StyleOverrides {
+    pub indent: Option<IndentRule>,
+    #[serde(alias = "lineEnding")]
+    pub line_ending: Option<LineEnding>,
+    #[serde(alias = "trailingNewline")]
+    pub trailing_newline: Option<bool>,
+    #[serde(alias = "escapeUnicode")]
+    pub escape_unicode: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IndentRule {
+    Spaces(usize),
+    Named(String),
+}
+
+impl IndentRule {
+    fn to_indent(&self) -> Result<Indent, CommandError> {
+        match self {
+            Self::Spaces(width) => Ok(Indent::Spaces(*width)),
+            Self::Named(name) => match name.as_str() {
+                "tab" | "tabs" => Ok(Indent::Tabs),
+                "compact" => Ok(Indent::Compact),
+                other => Err(CommandError::invalid_input(format!(
+                    "Unknown indent '{}', expected a number, \"tab\" or \"compact\"",
+                    other
+                ))),
+            },
+        }
+    }
+}
+
+impl Default for JsonStyle {
+    // What JSON.stringify(value, null, 2) produces
+    fn default() -> Self {
+        Self {
+            indent: Indent::Spaces(2),
+            line_ending: LineEnding::Lf,
+            trailing_newline: false,
+            escape_unicode: false,
+        }
+    }
+}
+
+impl JsonStyle {
+    pub fn detect(text: &str) -> Self {
+        let line_ending = if text.contains("\r\n") {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        };
+
+        Self {
+            indent: detect_indent(text),
+            line_ending,
+            trailing_newline: text.ends_with('\n'),
+            escape_unicode: detect_escaped_unicode(text),
+        }
+    }
+
+    pub fn with_overrides(mut self, overrides: &StyleOverrides) -> Result<Self, CommandError> {
+        if let Some(indent) = &overrides.indent {
+            self.indent = indent.to_indent()?;
+        }
+        if let Some(line_ending) = overrides.line_ending {
+            self.line_ending = line_ending;
+        }
+        if let Some(trailing_newline) = overrides.trailing_newline {
+            self.trailing_newline = trailing_newline;
+        }
+        if let Some(escape_unicode) = overrides.escape_unicode {
+            self.escape_unicode = escape_unicode;
+        }
+        Ok(self)
+    }
+
+    pub fn render(&self, value: &Value) -> Result<String, CommandError> {
+        let mut output = Vec::new();
+        let result = match self.indent {
+            Indent::Compact => value.serialize(&mut Serializer::with_formatter(
+                &mut output,
+                CompactFormatter,
+            )),
+            Indent::Spaces(width) => {
+                let indent = " ".repeat(width);
+                let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+                value.serialize(&mut Serializer::with_formatter(&mut output, formatter))
+            }
+            Indent::Tabs => {
+                let formatter = PrettyFormatter::with_indent(b"\t");
+                value.serialize(&mut Serializer::with_formatter(&mut output, formatter))
+            }
+        };
+        result.map_err(|e| CommandError::json("Failed to serialize JSON", e))?;
+
+        let mut text = String::from_utf8(output)
+            .map_err(|e| CommandError::internal(format!("Serialized JSON is not UTF-8: {}", e)))?;
+
+        // serde_json escapes control characters inside strings, so every
+        // newline and every non-ASCII character left is safe to rewrite
+        if self.escape_unicode {
+            text = escape_non_ascii(&text);
+        }
+        if self.trailing_newline {
+            text.push('\n');
+        }
+        if self.line_ending == LineEnding::CrLf {
+            text = text.replace('\n', "\r\n");
+        }
+
+        Ok(text)
+    }
+}
+
+// Indentation of the first indented line, or compact if the value is on one line
+fn detect_indent(text: &str) -> Indent {
+    if !text.trim().contains('\n') {
+        return Indent::Compact;
+    }
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with('\t') {
+            return Indent::Tabs;
+        }
+        let spaces = line.len() - line.trim_start_matches(' ').len();
+        if spaces > 0 {
+            return Indent::Spaces(spaces);
+        }
+    }
+
+    JsonStyle::default().indent
+}
+
+// True if non-ASCII text is written as \uXXXX escapes rather than raw
+fn detect_escaped_unicode(text: &str) -> bool {
+    if !text.is_ascii() {
+        return false;
+    }
+
+    text.match_indices("\\u").any(|(index, _)| {
+        // Skip escaped backslashes followed by a literal "u"
+        let backslashes = text[..index]
+            .chars()
+            .rev()
+            .take_while(|&c| c == '\\')
+            .count();
+        backslashes % 2 == 0
+            && text
+                .get(index + 2..index + 6)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .is_some_and(|code| code >= 0x80)
+    })
+}
+
+fn escape_non_ascii(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut units = [0u16; 2];
+
+    for c in text.chars() {
+        if c.is_ascii() {
+            escaped.push(c);
+        } else {
+            for unit in c.encode_utf16(&mut units) {
+                escaped.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Render `text`'s own value in its detected style
+    fn rerender(text: &str) -> String {
+        let value: Value = serde_json::from_str(text).unwrap();
+        JsonStyle::detect(text).render(&value).unwrap()
+    }
+
+    #[test]
+    fn detected_styles_reproduce_the_file() {
+        for text in [
+            "{\n\t\"home\": {\n\t\t\"title\": \"Start\"\n\t}\n}\n",
+            "{\n  \"home\": {\n    \"title\": \"Start\"\n  }\n}",
+            "{\n    \"home\": {\n        \"title\": \"Start\"\n    }\n}\n",
+            "{\r\n  \"home\": {\r\n    \"title\": \"Größe\"\r\n  }\r\n}\r\n",
+            "{\"home\":{\"title\":\"Start\"}}",
+            "{\n  \"title\": \"Gr\\u00f6\\u00dfe\"\n}\n",
+        ] {
+            assert_eq!(rerender(text), text);
+        }
+    }
+
+    #[test]
+    fn detection_reads_each_property() {
+        let style = JsonStyle::detect("{\r\n\t\"a\": \"é\"\r\n}");
+        assert_eq!(style.indent, Indent::Tabs);
+        assert_eq!(style.line_ending, LineEnding::CrLf);
+        assert!(!style.trailing_newline);
+        assert!(!style.escape_unicode);
+
+        let style = JsonStyle::detect("{\n    \"a\": [\n        1\n    ]\n}\n");
+        assert_eq!(style.indent, Indent::Spaces(4));
+        assert_eq!(style.line_ending, LineEnding::Lf);
+        assert!(style.trailing_newline);
+    }
+
+    #[test]
+    fn overrides_win_over_the_detected_style() {
+        let overrides: StyleOverrides =
+            serde_json::from_value(json!({ "indent": "tab", "trailing_newline": true })).unwrap();
+        let style = JsonStyle::detect("{\n  \"a\": 1\n}")
+            .with_overrides(&overrides)
+            .unwrap();
+
+        assert_eq!(
+            style.render(&json!({ "a": 1 })).unwrap(),
+            "{\n\t\"a\": 1\n}\n"
+        );
+
+        let unknown: StyleOverrides = serde_json::from_value(json!({ "indent": "wide" })).unwrap();
+        assert!(JsonStyle::default().with_overrides(&unknown).is_err());
+    }
+}
@@ -1,125 +1,795 @@
+mod backups;
+mod conflicts;
+mod encoding;
+mod error;
+mod formats;
+mod fs_util;
+mod json_style;
+mod locale_json;
+mod project;
+mod recent;
+mod scope;
+mod secure_storage;
+mod watcher;
+
 use tauri::{Manager, Emitter};
 use std::fs;
-use std::path::PathBuf;
-use base64::{Engine as _, engine::general_purpose};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use serde_json::json;
+use formats::{Format, RenderOptions};
+use fs_util::FileMode;
+use json_style::{JsonStyle, StyleOverrides};
+use locale_json::LoadedLocaleFile;
+use project::config::LoadedProjectConfig;
+use project::namespaces::{LoadedNamespaces, NamespaceProject, NamespaceTarget};
+use project::scan::ProjectMap;
+use project::template::{OutputPreview, OutputTemplate};
+use project::LanguageMatcher;
+use secure_storage::bundle::{self, ExportSummary, ImportSummary, MergeStrategy};
+use backups::BackupInfo;
+use conflicts::FileTracker;
+use encoding::{DecodedText, Encoding};
+use error::CommandError;
+use recent::{RecentEntry, RecentKind, RecentList};
+use scope::{ProjectScope, ScopeRegistry};
+use watcher::SourceWatcher;
+use secure_storage::{
+    BackendPreference, BackendStatus, SecretStore, SecureStorage, StoredKeyInfo, Vault,
+    VaultStatus,
+};
 #[cfg(target_os = "macos")]
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
 
 #[cfg(target_os = "windows")]
 use window_vibrancy::apply_blur;
 
+// Helper function to get the app data directory
+fn get_app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, CommandError> {
+    app.path().app_data_dir()
+        .map_err(|e| CommandError::internal(format!("Failed to get app data dir: {}", e)))
+}
+
 // Helper function to get storage file path
-fn get_storage_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app.path().app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+fn get_storage_path(app: &tauri::AppHandle) -> Result<PathBuf, CommandError> {
+    let app_data_dir = get_app_data_dir(app)?;
 
     let keys_dir = app_data_dir.join(".keys");
 
-    // Create the .keys directory if it doesn't exist
-    fs::create_dir_all(&keys_dir)
-        .map_err(|e| format!("Failed to create keys directory: {}", e))?;
+    // Create the .keys directory if it doesn't exist, readable by the owner only
+    fs_util::create_private_dir(&keys_dir)
+        .map_err(|e| CommandError::io("Failed to create keys directory", e))?;
 
     Ok(keys_dir)
 }
 
-// Secure storage commands using file-based storage
-// Files are stored in app data directory with base64 encoding
+// Helper function to resolve the active secret store (OS keyring or encrypted files)
+fn get_secret_store(app: &tauri::AppHandle) -> Result<Arc<dyn SecretStore>, CommandError> {
+    let storage_path = get_storage_path(app)?;
+    app.state::<SecureStorage>()
+        .store(&storage_path, &app.config().identifier)
+}
+
+// Secure storage commands, backed by whichever SecretStore is active.
+// Key names are validated before they reach a store.
+// When the vault is enabled, values are sealed/opened with the vault key
+// around the store and a locked vault refuses both.
 #[tauri::command]
-fn secure_storage_get(app: tauri::AppHandle, key: String) -> Result<String, String> {
+fn secure_storage_get(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    key: String,
+) -> Result<String, CommandError> {
+    let key = secure_storage::parse_key(&key)?;
     let storage_path = get_storage_path(&app)?;
-    let key_file = storage_path.join(format!("{}.dat", key));
+    let stored = get_secret_store(&app)?.get(&key)?;
+    vault.open(&storage_path, &key, &stored)
+}
 
-    if !key_file.exists() {
-        return Err(format!("Key '{}' not found", key));
-    }
+#[tauri::command]
+fn secure_storage_set(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    key: String,
+    value: String,
+) -> Result<(), CommandError> {
+    let key = secure_storage::parse_key(&key)?;
+    let storage_path = get_storage_path(&app)?;
+    let stored = vault.seal(&storage_path, &key, &value)?;
+    get_secret_store(&app)?.set(&key, &stored)?;
+    secure_storage::metadata::record_write(&storage_path, &key)
+}
 
-    match fs::read_to_string(&key_file) {
-        Ok(encoded) => {
-            // Decode from base64
-            match general_purpose::STANDARD.decode(&encoded) {
-                Ok(decoded_bytes) => {
-                    match String::from_utf8(decoded_bytes) {
-                        Ok(value) => Ok(value),
-                        Err(e) => Err(format!("Failed to decode value: {}", e))
-                    }
-                },
-                Err(e) => Err(format!("Failed to decode base64: {}", e))
-            }
-        },
-        Err(e) => Err(format!("Failed to read file: {}", e))
-    }
+#[tauri::command]
+fn secure_storage_remove(app: tauri::AppHandle, key: String) -> Result<(), CommandError> {
+    let key = secure_storage::parse_key(&key)?;
+    let storage_path = get_storage_path(&app)?;
+    get_secret_store(&app)?.remove(&key)?;
+    secure_storage::metadata::record_remove(&storage_path, &key)
 }
 
 #[tauri::command]
-fn secure_storage_set(app: tauri::AppHandle, key: String, value: String) -> Result<(), String> {
+fn secure_storage_list(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+) -> Result<Vec<StoredKeyInfo>, CommandError> {
     let storage_path = get_storage_path(&app)?;
-    let key_file = storage_path.join(format!("{}.dat", key));
+    let store = get_secret_store(&app)?;
+    secure_storage::list_keys(&storage_path, store.as_ref(), &vault)
+}
+
+#[tauri::command]
+fn secure_storage_clear(app: tauri::AppHandle) -> Result<usize, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    let store = get_secret_store(&app)?;
+    secure_storage::clear_keys(&storage_path, store.as_ref())
+}
+
+// Export/import of all secrets (plus frontend preferences) as one
+// passphrase-protected bundle. The bundle path is picked here with a native
+// dialog rather than taken from the webview, so a script can't point either
+// command at an arbitrary file. Both return None when the dialog is cancelled.
+#[tauri::command]
+async fn secure_storage_export(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    passphrase: String,
+    preferences: Option<serde_json::Value>,
+) -> Result<Option<ExportSummary>, CommandError> {
+    use tauri_plugin_dialog::DialogExt;
+    use std::sync::mpsc;
+
+    let window = app.get_webview_window("main")
+        .ok_or_else(|| CommandError::not_found("Main window not found"))?;
+
+    let (tx, rx) = mpsc::channel();
+
+    window.dialog()
+        .file()
+        .add_filter("LocaleKit Bundle", &[bundle::BUNDLE_EXTENSION])
+        .set_file_name(format!("localekit-keys.{}", bundle::BUNDLE_EXTENSION))
+        .save_file(move |file_path| {
+            let _ = tx.send(file_path);
+        });
 
-    // Encode to base64
-    let encoded = general_purpose::STANDARD.encode(value.as_bytes());
+    // Wait for the callback
+    let path = match rx.recv() {
+        Ok(Some(file_path)) => file_path
+            .into_path()
+            .map_err(|e| CommandError::invalid_input(format!("Unsupported file path: {}", e)))?,
+        _ => return Ok(None),
+    };
 
-    fs::write(&key_file, encoded)
-        .map_err(|e| format!("Failed to write file: {}", e))
+    let storage_path = get_storage_path(&app)?;
+    let store = get_secret_store(&app)?;
+    bundle::export(
+        &path,
+        &passphrase,
+        preferences,
+        &storage_path,
+        store.as_ref(),
+        &vault,
+    )
+    .map(Some)
 }
 
 #[tauri::command]
-fn secure_storage_remove(app: tauri::AppHandle, key: String) -> Result<(), String> {
+async fn secure_storage_import(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    passphrase: String,
+    merge_strategy: MergeStrategy,
+) -> Result<Option<ImportSummary>, CommandError> {
+    use tauri_plugin_dialog::DialogExt;
+    use std::sync::mpsc;
+
+    let window = app.get_webview_window("main")
+        .ok_or_else(|| CommandError::not_found("Main window not found"))?;
+
+    let (tx, rx) = mpsc::channel();
+
+    window.dialog()
+        .file()
+        .add_filter("LocaleKit Bundle", &[bundle::BUNDLE_EXTENSION])
+        .pick_file(move |file_path| {
+            let _ = tx.send(file_path);
+        });
+
+    // Wait for the callback
+    let path = match rx.recv() {
+        Ok(Some(file_path)) => file_path
+            .into_path()
+            .map_err(|e| CommandError::invalid_input(format!("Unsupported file path: {}", e)))?,
+        _ => return Ok(None),
+    };
+
+    let storage_path = get_storage_path(&app)?;
+    let store = get_secret_store(&app)?;
+    bundle::import(
+        &path,
+        &passphrase,
+        merge_strategy,
+        &storage_path,
+        store.as_ref(),
+        &vault,
+    )
+    .map(Some)
+}
+
+#[tauri::command]
+fn secure_storage_backend(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SecureStorage>,
+) -> Result<BackendStatus, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    state.status(&storage_path, &app.config().identifier)
+}
+
+#[tauri::command]
+fn secure_storage_set_backend(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SecureStorage>,
+    preference: BackendPreference,
+) -> Result<BackendStatus, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    state.set_preference(&storage_path, &app.config().identifier, preference)
+}
+
+// Vault commands. Key derivation is deliberately slow, so the commands that
+// take a passphrase run off the main thread.
+#[tauri::command]
+fn vault_status(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+) -> Result<VaultStatus, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    vault.status(&storage_path)
+}
+
+#[tauri::command]
+async fn vault_enable(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    passphrase: String,
+    auto_lock_seconds: Option<u64>,
+) -> Result<VaultStatus, CommandError> {
     let storage_path = get_storage_path(&app)?;
-    let key_file = storage_path.join(format!("{}.dat", key));
+    let store = get_secret_store(&app)?;
+    vault.enable(&storage_path, store.as_ref(), &passphrase, auto_lock_seconds)
+}
+
+#[tauri::command]
+async fn vault_disable(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    passphrase: String,
+) -> Result<VaultStatus, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    let store = get_secret_store(&app)?;
+    vault.disable(&storage_path, store.as_ref(), &passphrase)
+}
+
+#[tauri::command]
+async fn vault_unlock(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    passphrase: String,
+) -> Result<VaultStatus, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    vault.unlock(&storage_path, &passphrase)
+}
+
+#[tauri::command]
+fn vault_lock(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+) -> Result<VaultStatus, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    if vault.lock()? {
+        let _ = app.emit("vault-locked", ());
+    }
+    vault.status(&storage_path)
+}
+
+#[tauri::command]
+fn vault_set_auto_lock(
+    app: tauri::AppHandle,
+    vault: tauri::State<'_, Vault>,
+    auto_lock_seconds: u64,
+) -> Result<VaultStatus, CommandError> {
+    let storage_path = get_storage_path(&app)?;
+    vault.set_auto_lock(&storage_path, auto_lock_seconds)
+}
+
+#[tauri::command]
+async fn select_source_file(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+) -> Result<Option<String>, CommandError> {
+    use tauri_plugin_dialog::DialogExt;
+    use std::sync::mpsc;
+
+    let window = app.get_webview_window("main")
+        .ok_or_else(|| CommandError::not_found("Main window not found"))?;
 
-    if !key_file.exists() {
-        return Ok(()); // Not an error if it doesn't exist
+    let (tx, rx) = mpsc::channel();
+
+    let all_extensions: Vec<&str> = formats::DIALOG_FILTERS
+        .iter()
+        .flat_map(|(_, extensions)| extensions.iter().copied())
+        .collect();
+    let mut dialog = window.dialog()
+        .file()
+        .add_filter("Locale Files", &all_extensions);
+    for (name, extensions) in formats::DIALOG_FILTERS {
+        dialog = dialog.add_filter(*name, extensions);
     }
+    dialog.pick_file(move |file_path| {
+        let _ = tx.send(file_path);
+    });
 
-    fs::remove_file(&key_file)
-        .map_err(|e| format!("Failed to delete file: {}", e))
+    // Wait for the callback
+    let file_path = match rx.recv() {
+        Ok(Some(file_path)) => file_path
+            .into_path()
+            .map_err(|e| CommandError::invalid_input(format!("Unsupported file path: {}", e)))?,
+        _ => return Ok(None),
+    };
+
+    // The user picked this file, so it and the files next to it become
+    // readable/writable, see scope.rs
+    scope.grant_project(&get_app_data_dir(&app)?, &file_path)?;
+
+    Ok(Some(file_path.to_string_lossy().into_owned()))
 }
 
 #[tauri::command]
-async fn select_source_file(app: tauri::AppHandle) -> Result<Option<String>, String> {
+async fn select_project_directory(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+) -> Result<Option<String>, CommandError> {
     use tauri_plugin_dialog::DialogExt;
     use std::sync::mpsc;
 
     let window = app.get_webview_window("main")
-        .ok_or_else(|| "Main window not found".to_string())?;
+        .ok_or_else(|| CommandError::not_found("Main window not found"))?;
 
     let (tx, rx) = mpsc::channel();
 
     window.dialog()
         .file()
-        .add_filter("JSON Files", &["json"])
-        .pick_file(move |file_path| {
-            let _ = tx.send(file_path);
+        .pick_folder(move |folder_path| {
+            let _ = tx.send(folder_path);
         });
 
     // Wait for the callback
-    match rx.recv() {
-        Ok(file_path) => Ok(file_path.map(|p| p.to_string())),
-        Err(_) => Ok(None),
+    let directory = match rx.recv() {
+        Ok(Some(directory)) => directory
+            .into_path()
+            .map_err(|e| CommandError::invalid_input(format!("Unsupported directory path: {}", e)))?,
+        _ => return Ok(None),
+    };
+
+    scope.grant_directory(&get_app_data_dir(&app)?, &directory)?;
+
+    Ok(Some(directory.to_string_lossy().into_owned()))
+}
+
+// Find the locale files under a project directory, see project/scan.rs.
+// `languages` is the frontend's list of language codes.
+#[tauri::command]
+async fn scan_locale_directory(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    dir: String,
+    languages: Vec<String>,
+    source_language: Option<String>,
+) -> Result<ProjectMap, CommandError> {
+    let data_dir = get_app_data_dir(&app)?;
+    let resolved = scope.check(&data_dir, &dir)?;
+    let matcher = LanguageMatcher::new(&languages);
+    let map = project::scan::scan(&resolved, &matcher, source_language.as_deref())?;
+
+    // Writes inside a directory grant are limited to the groups found here
+    scope.record_scan(&data_dir, &map, &languages)?;
+    Ok(map)
+}
+
+// File commands only touch paths inside a granted project, see scope.rs
+#[tauri::command]
+fn read_json_file(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    tracker: tauri::State<'_, FileTracker>,
+    path: String,
+) -> Result<DecodedText, CommandError> {
+    let resolved = scope.check(&get_app_data_dir(&app)?, &path)?;
+    tracker.record(&resolved)?;
+
+    encoding::read_text(&resolved).map_err(|e| e.with_details(json!({ "path": path })))
+}
+
+// Parse a locale file in any supported format (see formats/), reporting
+// syntax errors with their location and duplicate keys as warnings
+#[tauri::command]
+fn load_locale_file(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    tracker: tauri::State<'_, FileTracker>,
+    path: String,
+) -> Result<LoadedLocaleFile, CommandError> {
+    let resolved = scope.check(&get_app_data_dir(&app)?, &path)?;
+    tracker.record(&resolved)?;
+
+    let text = encoding::read_text(&resolved).map_err(|e| e.with_details(json!({ "path": path })))?;
+    formats::load(Format::from_path(&resolved), &text.content, text.encoding)
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WriteOptions {
+    // File whose formatting and encoding the output should match
+    source_path: Option<String>,
+    // Number of backups to keep, see backups.rs
+    keep_backups: Option<usize>,
+    // Write even if the file changed since it was read or checked
+    #[serde(default)]
+    overwrite: bool,
+    // Formatting rules that win over the detected style, e.g. from the
+    // project config
+    format: Option<StyleOverrides>,
+    // Language of the file, for formats that record it (e.g. the Language
+    // and Plural-Forms headers of a PO file)
+    language: Option<String>,
+    // Mark the translations for review, in formats that can (e.g. because
+    // the run reported a warning)
+    #[serde(default)]
+    needs_review: bool,
+}
+
+// Fails with a Conflict error if the file changed since it was read or
+// checked, unless `overwrite` is set
+#[tauri::command]
+fn write_json_file(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    tracker: tauri::State<'_, FileTracker>,
+    path: String,
+    content: String,
+    options: Option<WriteOptions>,
+) -> Result<(), CommandError> {
+    let data_dir = get_app_data_dir(&app)?;
+    let resolved = scope.check_write(&data_dir, &path)?;
+
+    let options = options.unwrap_or_default();
+    write_locale_file(&scope, &tracker, &data_dir, &resolved, content, options)
+}
+
+// Shared by the commands that write locale files. `path` must already have
+// passed the scope check.
+fn write_locale_file(
+    scope: &ScopeRegistry,
+    tracker: &FileTracker,
+    data_dir: &Path,
+    path: &Path,
+    content: String,
+    options: WriteOptions,
+) -> Result<(), CommandError> {
+    // Refuse to replace a locale file with something that isn't JSON
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| CommandError::json("Content is not valid JSON", e))?;
+
+    // The source file, or else the file being replaced, decides formatting
+    // and encoding. Formats other than JSON are built from it.
+    let reference = match options.source_path {
+        Some(source_path) => {
+            let source = scope.check(data_dir, &source_path)?;
+            Some(
+                encoding::read_text(&source)
+                    .map_err(|e| e.with_details(json!({ "path": source_path })))?,
+            )
+        }
+        None => encoding::read_text(path).ok(),
+    };
+    let (content, file_encoding) = match Format::from_path(path) {
+        Format::Json => render_json(reference, options.format.as_ref(), &value, content)?,
+        format => {
+            let source = reference.ok_or_else(|| {
+                CommandError::invalid_input("This format can only be written from a source file")
+            })?;
+            let render_options = RenderOptions {
+                language: options.language.as_deref(),
+                needs_review: options.needs_review,
+            };
+            let content = formats::render(format, &source.content, &value, &render_options)
+                .map_err(|e| e.with_details(json!({ "path": path })))?;
+            let file_encoding = match format {
+                // The catalog's Content-Type header says UTF-8, see formats/po.rs
+                Format::Po => Encoding::default(),
+                _ => source.encoding,
+            };
+            (content, file_encoding)
+        }
+    };
+
+    if !options.overwrite {
+        tracker.check_unchanged(path, &content)?;
     }
+
+    let bytes = encoding::encode(&content, file_encoding);
+    let keep_backups = options.keep_backups.unwrap_or(backups::DEFAULT_KEEP);
+    backups::backup_before_write(path, &bytes, keep_backups)?;
+
+    // Folder-per-locale layouts may need the target folder created
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            CommandError::io("Failed to create directory", e).with_details(json!({ "path": parent }))
+        })?;
+    }
+
+    fs_util::write_atomic(path, &bytes, FileMode::Default).map_err(|e| {
+        CommandError::io("Failed to write file", e).with_details(json!({ "path": path }))
+    })?;
+
+    tracker.record(path)
+}
+
+// Match the formatting and encoding of `reference`, then apply any explicit
+// formatting rules. New files without a reference or rules keep the content
+// as given and are written as UTF-8.
+fn render_json(
+    reference: Option<DecodedText>,
+    format: Option<&StyleOverrides>,
+    value: &serde_json::Value,
+    content: String,
+) -> Result<(String, Encoding), CommandError> {
+    let (style, file_encoding) = match reference {
+        Some(reference) => (
+            Some(JsonStyle::detect(&reference.content)),
+            reference.encoding,
+        ),
+        None => (None, Encoding::default()),
+    };
+    let style = match format {
+        Some(format) => Some(style.unwrap_or_default().with_overrides(format)?),
+        None => style,
+    };
+    let content = match style {
+        Some(style) => style.render(value)?,
+        None => content,
+    };
+
+    Ok((content, file_encoding))
 }
 
+// Settings pinned by a localekit.toml or .localekit.json next to the source
+// file, see project/config.rs. Returns null if there is none.
 #[tauri::command]
-fn read_json_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))
+fn load_project_config(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    source_path: String,
+) -> Result<Option<LoadedProjectConfig>, CommandError> {
+    let source = scope.check(&get_app_data_dir(&app)?, &source_path)?;
+    project::config::load(&source)
 }
 
+// Output path templates, see project/template.rs. Without a template the
+// translated file goes next to the source as `<lang><ext>` (Android and Apple
+// resources go into per-language folders). String catalogs are translated in
+// place, whatever the template.
 #[tauri::command]
-fn write_json_file(path: String, content: String) -> Result<(), String> {
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write file: {}", e))
+fn preview_output_paths(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    template: Option<String>,
+    source_path: String,
+    languages: Vec<String>,
+) -> Result<Vec<OutputPreview>, CommandError> {
+    let data_dir = get_app_data_dir(&app)?;
+    let source = scope.check(&data_dir, &source_path)?;
+    if Format::from_path(&source).writes_in_place() {
+        return Ok(languages
+            .into_iter()
+            .map(|language| OutputPreview {
+                language,
+                path: Some(source.clone()),
+                exists: true,
+                in_scope: true,
+                error: None,
+            })
+            .collect());
+    }
+    let template =
+        OutputTemplate::parse(template.as_deref().unwrap_or(formats::default_template(&source)))?;
+
+    Ok(template.preview(&source, &languages, |path| {
+        scope.check_write(&data_dir, &path.to_string_lossy()).is_ok()
+    }))
 }
 
 #[tauri::command]
-fn check_file_exists(path: String) -> Result<bool, String> {
-    Ok(fs::metadata(&path).is_ok())
+fn resolve_output_path(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    template: Option<String>,
+    source_path: String,
+    language: String,
+) -> Result<String, CommandError> {
+    let data_dir = get_app_data_dir(&app)?;
+    let source = scope.check(&data_dir, &source_path)?;
+    if Format::from_path(&source).writes_in_place() {
+        return Ok(source.to_string_lossy().into_owned());
+    }
+    let template =
+        OutputTemplate::parse(template.as_deref().unwrap_or(formats::default_template(&source)))?;
+
+    let path = template.resolve(&source, &language)?;
+    let path = scope.check_write(&data_dir, &path.to_string_lossy())?;
+    Ok(path.to_string_lossy().into_owned())
 }
 
+// Load every namespace of the source language, see project/namespaces.rs
 #[tauri::command]
-fn close_window(window: tauri::Window) -> Result<(), String> {
+fn load_project_namespaces(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    base: String,
+    languages: Vec<String>,
+    source_language: String,
+) -> Result<LoadedNamespaces, CommandError> {
+    let resolved = scope.check(&get_app_data_dir(&app)?, &base)?;
+    let matcher = LanguageMatcher::new(&languages);
+    let project = NamespaceProject::open(&resolved, &matcher, &source_language)?;
+    project::namespaces::load_source(project)
+}
+
+// Write one translated namespace into its language folder, creating the
+// folder if needed. Returns the path written.
+#[tauri::command]
+fn write_project_namespace(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    tracker: tauri::State<'_, FileTracker>,
+    target: NamespaceTarget,
+    languages: Vec<String>,
+    content: String,
+    options: Option<WriteOptions>,
+) -> Result<String, CommandError> {
+    let data_dir = get_app_data_dir(&app)?;
+    let base = scope.check(&data_dir, &target.base)?;
+    let matcher = LanguageMatcher::new(&languages);
+    let project = NamespaceProject::open(&base, &matcher, &target.source_language)?;
+
+    let path = project.target_file(&matcher, &target.language, &target.namespace)?;
+    let path = scope.check_write(&data_dir, &path.to_string_lossy())?;
+
+    // Match the formatting of the namespace's source file by default
+    let mut options = options.unwrap_or_default();
+    if options.source_path.is_none() {
+        let source_file = project.source_file(&target.namespace)?;
+        options.source_path = Some(source_file.to_string_lossy().into_owned());
+    }
+
+    write_locale_file(&scope, &tracker, &data_dir, &path, content, options)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+fn check_file_exists(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    tracker: tauri::State<'_, FileTracker>,
+    path: String,
+) -> Result<bool, CommandError> {
+    let resolved = scope.check(&get_app_data_dir(&app)?, &path)?;
+    tracker.record(&resolved)?;
+    Ok(fs::metadata(&resolved).is_ok())
+}
+
+// Emit `source-file-changed` with the changed keys whenever the file is
+// saved, see watcher.rs
+#[tauri::command]
+fn watch_source_file(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    watcher: tauri::State<'_, SourceWatcher>,
+    path: String,
+) -> Result<(), CommandError> {
+    let resolved = scope.check(&get_app_data_dir(&app)?, &path)?;
+    watcher.watch(app.clone(), resolved)
+}
+
+#[tauri::command]
+fn unwatch_source_file(watcher: tauri::State<'_, SourceWatcher>) -> Result<bool, CommandError> {
+    watcher.unwatch()
+}
+
+#[tauri::command]
+fn list_backups(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    path: String,
+) -> Result<Vec<BackupInfo>, CommandError> {
+    let resolved = scope.check(&get_app_data_dir(&app)?, &path)?;
+    backups::list(&resolved)
+}
+
+#[tauri::command]
+fn restore_backup(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    tracker: tauri::State<'_, FileTracker>,
+    path: String,
+    backup: String,
+    keep_backups: Option<usize>,
+) -> Result<(), CommandError> {
+    let data_dir = get_app_data_dir(&app)?;
+    let resolved = scope.check_write(&data_dir, &path)?;
+    // Backups live below the file's directory, which a file grant doesn't
+    // cover; backups::restore only accepts backups of `resolved`
+    let backup = scope::normalize(Path::new(&backup))
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid backup path: {}", backup)))?;
+
+    backups::restore(&resolved, &backup, keep_backups.unwrap_or(backups::DEFAULT_KEEP))?;
+    tracker.record(&resolved)
+}
+
+#[tauri::command]
+fn scope_list(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+) -> Result<Vec<ProjectScope>, CommandError> {
+    scope.projects(&get_app_data_dir(&app)?)
+}
+
+#[tauri::command]
+fn scope_revoke(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    source: String,
+) -> Result<bool, CommandError> {
+    scope.revoke(&get_app_data_dir(&app)?, &source)
+}
+
+// Recently opened files and projects, see recent.rs. Only granted paths can
+// be added, so every entry can be reopened without a dialog.
+#[tauri::command]
+fn recent_list(
+    app: tauri::AppHandle,
+    recent: tauri::State<'_, RecentList>,
+) -> Result<Vec<RecentEntry>, CommandError> {
+    recent.list(&get_app_data_dir(&app)?)
+}
+
+#[tauri::command]
+fn recent_add(
+    app: tauri::AppHandle,
+    scope: tauri::State<'_, ScopeRegistry>,
+    recent: tauri::State<'_, RecentList>,
+    path: String,
+    kind: RecentKind,
+) -> Result<Vec<RecentEntry>, CommandError> {
+    let data_dir = get_app_data_dir(&app)?;
+    let path = scope.check(&data_dir, &path)?;
+    let entries = recent.add(&data_dir, path, kind)?;
+    recent.sync_menu(&app, &entries)?;
+    Ok(entries)
+}
+
+#[tauri::command]
+fn recent_clear(
+    app: tauri::AppHandle,
+    recent: tauri::State<'_, RecentList>,
+) -> Result<(), CommandError> {
+    recent.clear(&get_app_data_dir(&app)?)?;
+    recent.sync_menu(&app, &[])
+}
+
+#[tauri::command]
+fn close_window(window: tauri::Window) -> Result<(), CommandError> {
     window.close()
-        .map_err(|e| format!("Failed to close window: {}", e))
+        .map_err(|e| CommandError::internal(format!("Failed to close window: {}", e)))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -129,14 +799,49 @@ pub fn run() {
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
+        .manage(SecureStorage::default())
+        .manage(Vault::default())
+        .manage(ScopeRegistry::default())
+        .manage(FileTracker::default())
+        .manage(SourceWatcher::default())
+        .manage(RecentList::default())
         .invoke_handler(tauri::generate_handler![
             secure_storage_get,
             secure_storage_set,
             secure_storage_remove,
+            secure_storage_list,
+            secure_storage_clear,
+            secure_storage_export,
+            secure_storage_import,
+            secure_storage_backend,
+            secure_storage_set_backend,
+            vault_status,
+            vault_enable,
+            vault_disable,
+            vault_unlock,
+            vault_lock,
+            vault_set_auto_lock,
             select_source_file,
+            select_project_directory,
+            scan_locale_directory,
             read_json_file,
+            load_locale_file,
             write_json_file,
+            load_project_config,
+            preview_output_paths,
+            resolve_output_path,
+            load_project_namespaces,
+            write_project_namespace,
             check_file_exists,
+            watch_source_file,
+            unwatch_source_file,
+            list_backups,
+            restore_backup,
+            scope_list,
+            scope_revoke,
+            recent_list,
+            recent_add,
+            recent_clear,
             close_window
         ])
         .setup(|app| {
@@ -150,16 +855,38 @@ pub fn run() {
             let app_handle = app.handle().clone();
             #[cfg(target_os = "macos")]
             {
-                use tauri::menu::{Menu, MenuItem, Submenu};
-                // Create About and Quit menu items
+                use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+                // Create About, Open Recent and Quit menu items
                 let about_item = MenuItem::with_id(app, "about", "About LocaleKit", true, None::<&str>)?;
+                let recent_submenu = Submenu::with_id(app, "open-recent", "Open Recent", true)?;
                 let quit_item = MenuItem::with_id(app, "quit", "Quit LocaleKit", true, Some("q"))?;
-                // Create the app submenu with About and Quit items
-                let app_submenu = Submenu::with_items(app, "LocaleKit", true, &[&about_item, &quit_item])?;
+                // Create the app submenu with About, Open Recent and Quit items
+                let app_submenu = Submenu::with_items(
+                    app,
+                    "LocaleKit",
+                    true,
+                    &[
+                        &about_item,
+                        &PredefinedMenuItem::separator(app)?,
+                        &recent_submenu,
+                        &PredefinedMenuItem::separator(app)?,
+                        &quit_item,
+                    ],
+                )?;
                 // Create the main menu with the app submenu
                 let menu = Menu::with_items(app, &[&app_submenu])?;
                 // Set as the app menu
                 app.set_menu(menu)?;
+
+                // Fill Open Recent from the persisted list. A broken list
+                // shouldn't keep the app from starting.
+                let recent_result = get_app_data_dir(app.handle()).and_then(|data_dir| {
+                    app.state::<RecentList>()
+                        .attach_menu(app.handle(), &data_dir, recent_submenu)
+                });
+                if let Err(e) = recent_result {
+                    println!("Failed to build Open Recent menu: {}", e);
+                }
                 
                 // Handle menu events - intercept quit and about actions
                 app.on_menu_event(move |_app, event| {
@@ -181,12 +908,38 @@ pub fn run() {
             let app_handle_fallback = app.handle().clone();
             app.on_menu_event(move |_app, event| {
                 let event_id = event.id.as_ref();
+
+                // Open Recent entries and Clear Menu. Checked first, since
+                // entry ids contain arbitrary paths.
+                let recent_result = get_app_data_dir(&app_handle_fallback).and_then(|data_dir| {
+                    app_handle_fallback
+                        .state::<RecentList>()
+                        .handle_menu_event(&app_handle_fallback, &data_dir, event_id)
+                });
+                match recent_result {
+                    Ok(true) => return,
+                    Ok(false) => {}
+                    Err(e) => {
+                        println!("Failed to handle recent menu item: {}", e);
+                        return;
+                    }
+                }
+
                 if event_id == "quit" || event_id.contains("quit") {
                     println!("Fallback: Intercepting quit action, showing confirmation");
                     let _ = app_handle_fallback.emit("window-close-requested", ());
                 }
             });
 
+            // Auto-lock the vault once it has been idle past its timeout
+            let app_handle_vault = app.handle().clone();
+            std::thread::spawn(move || loop {
+                std::thread::sleep(std::time::Duration::from_secs(5));
+                if app_handle_vault.state::<Vault>().lock_if_idle() {
+                    let _ = app_handle_vault.emit("vault-locked", ());
+                }
+            });
+
             // Get window for all platforms
             let window = app.get_webview_window("main").unwrap();
 
@@ -0,0 +1,295 @@
+// Parsing locale files in Rust rather than with JSON.parse in the webview
+//
+// JSON.parse only reports "unexpected token" and silently keeps the last of
+// several values for the same key. Locale files are parsed here instead, so a
+// syntax error comes back with its line, column and a snippet of the
+// surrounding text, and duplicate keys are reported as warnings (the last
+// value still wins, as in JavaScript).
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use serde_json::{json, Map, Number, Value};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::encoding::Encoding;
+use crate::error::CommandError;
+
+// Lines of context shown before the offending line in a syntax error snippet
+const SNIPPET_CONTEXT_LINES: usize = 2;
+// Longest line shown in a snippet, in characters
+const SNIPPET_MAX_WIDTH: usize = 120;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum LoadWarning {
+    // `path` is the dotted path of the key, e.g. `home.title`
+    DuplicateKey { path: String, occurrences: usize },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedLocaleFile {
+    pub data: Value,
+    pub encoding: Encoding,
+    pub warnings: Vec<LoadWarning>,
+    // Notes for the translator by key path, e.g. the descriptions in an ARB
+    // file. Not part of the data.
+    pub context: BTreeMap<String, String>,
+}
+
+pub fn load(content: &str, encoding: Encoding) -> Result<LoadedLocaleFile, CommandError> {
+    // Occurrences of every key path seen more than once
+    let duplicates = RefCell::new(BTreeMap::new());
+
+    let mut deserializer = serde_json::Deserializer::from_str(content);
+    let data = ValueSeed {
+        path: String::new(),
+        duplicates: &duplicates,
+    }
+    .deserialize(&mut deserializer)
+    .and_then(|value| deserializer.end().map(|_| value))
+    .map_err(|e| syntax_error(content, e))?;
+
+    let warnings = duplicates
+        .into_inner()
+        .into_iter()
+        .map(|(path, occurrences)| LoadWarning::DuplicateKey { path, occurrences })
+        .collect();
+
+    Ok(LoadedLocaleFile {
+        data,
+        encoding,
+        warnings,
+        context: BTreeMap::new(),
+    })
+}
+
+fn syntax_error(content: &str, error: serde_json::Error) -> CommandError {
+    let (line, column) = (error.line(), error.column());
+    CommandError::json("Invalid JSON", error).with_details(json!({
+        "line": line,
+        "column": column,
+        "snippet": snippet(content, line, column),
+    }))
+}
+
+// The offending line with a few lines before it and a caret under the column
+fn snippet(content: &str, line: usize, column: usize) -> String {
+    if line == 0 {
+        return String::new();
+    }
+
+    let first = line.saturating_sub(SNIPPET_CONTEXT_LINES).max(1);
+    let width = line.to_string().len();
+    let mut snippet = String::new();
+
+    for (number, text) in content.lines().enumerate().map(|(i, text)| (i + 1, text)) {
+        if number < first {
+            continue;
+        }
+        if number > line {
+            break;
+        }
+
+        let text: String = text.chars().take(SNIPPET_MAX_WIDTH).collect();
+        snippet.push_str(&format!("{:>width$} | {}\n", number, text, width = width));
+    }
+
+    let caret_offset = column.saturating_sub(1).min(SNIPPET_MAX_WIDTH);
+    snippet.push_str(&format!(
+        "{:>width$} | {}^",
+        "",
+        " ".repeat(caret_offset),
+        width = width
+    ));
+    snippet
+}
+
+// Builds a serde_json::Value like Value::deserialize does, recording object
+// keys that appear more than once along the way
+struct ValueSeed<'a> {
+    path: String,
+    duplicates: &'a RefCell<BTreeMap<String, usize>>,
+}
+
+impl ValueSeed<'_> {
+    fn child(&self, segment: &str) -> Self {
+        let path = if self.path.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}.{}", self.path, segment)
+        };
+        ValueSeed {
+            path,
+            duplicates: self.duplicates,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element_seed(self.child(&values.len().to_string()))? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut object = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let child = self.child(&key);
+            let path = child.path.clone();
+            let value = map.next_value_seed(child)?;
+
+            if object.insert(key, value).is_some() {
+                *self.duplicates.borrow_mut().entry(path).or_insert(1) += 1;
+            }
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+// Every leaf value of `value` keyed by its dotted path, e.g. `home.title`
+pub fn leaves(value: &Value) -> BTreeMap<String, &Value> {
+    let mut leaves = BTreeMap::new();
+    collect_leaves(value, String::new(), &mut leaves);
+    leaves
+}
+
+fn collect_leaves<'a>(value: &'a Value, path: String, leaves: &mut BTreeMap<String, &'a Value>) {
+    let join = |segment: &str| {
+        if path.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}.{}", path, segment)
+        }
+    };
+
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, child) in object {
+                collect_leaves(child, join(key), leaves);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (index, child) in array.iter().enumerate() {
+                collect_leaves(child, join(&index.to_string()), leaves);
+            }
+        }
+        _ => {
+            leaves.insert(path, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_duplicate_keys_are_reported_and_the_last_value_wins() {
+        let content = r#"{
+  "home": {
+    "nav": { "title": "One", "title": "Two", "title": "Three" },
+    "body": "Text"
+  },
+  "home": { "nav": { "title": "Four" } },
+  "list": [{ "a": 1, "a": 2 }]
+}"#;
+
+        let loaded = load(content, Encoding::default()).unwrap();
+        assert_eq!(
+            loaded.data,
+            json!({ "home": { "nav": { "title": "Four" } }, "list": [{ "a": 2 }] })
+        );
+        let warnings: Vec<(String, usize)> = loaded
+            .warnings
+            .into_iter()
+            .map(|warning| match warning {
+                LoadWarning::DuplicateKey { path, occurrences } => (path, occurrences),
+            })
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                ("home".to_string(), 2),
+                ("home.nav.title".to_string(), 3),
+                ("list.0.a".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn syntax_errors_point_at_the_offending_line_and_column() {
+        let content = "{\n  \"title\": \"Inbox\",\n  \"body\": \"Text\"\n  \"footer\": \"End\"\n}";
+
+        let error = load(content, Encoding::default()).unwrap_err();
+        let details = error.details.unwrap();
+        assert_eq!(details["line"], 4);
+        assert_eq!(details["column"], 3);
+        assert_eq!(
+            details["snippet"],
+            "2 |   \"title\": \"Inbox\",\n3 |   \"body\": \"Text\"\n4 |   \"footer\": \"End\"\n  |   ^"
+        );
+    }
+
+    #[test]
+    fn trailing_content_is_a_syntax_error() {
+        let error = load("{}\n{}", Encoding::default()).unwrap_err();
+        let details = error.details.unwrap();
+        assert_eq!(details["line"], 2);
+        assert_eq!(details["column"], 1);
+    }
+}
@@ -0,0 +1,217 @@
+// Per-repository project config
+//
+// A `localekit.toml` (or `.localekit.json`) next to the source file pins the
+// settings of a translation run, so every team member gets the same result:
+//
+//   source_locale = "en_gb"
+//   target_languages = ["de_de", "fr_fr"]
+//   excluded_paths = ["meta.version"]
+//   model = "gpt-4o-mini"
+//   output_template = "{dir}/{language}/{basename}"
+//   glossary = "docs/glossary.csv"
+//
+//   [format]
+//   indent = 4
+//   line_ending = "lf"
+//   trailing_newline = true
+//
+// Keys are snake_case; the JSON file may use camelCase instead. Every key is
+// optional. Relative paths are resolved against the config's directory.
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::template::OutputTemplate;
+use crate::error::CommandError;
+use crate::json_style::{JsonStyle, StyleOverrides};
+
+// Looked up in this order
+pub const CONFIG_FILE_NAMES: &[&str] = &["localekit.toml", ".localekit.json"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all(serialize = "camelCase", deserialize = "snake_case"))]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(alias = "sourceLocale")]
+    pub source_locale: Option<String>,
+    #[serde(default, alias = "targetLanguages")]
+    pub target_languages: Vec<String>,
+    #[serde(default, alias = "excludedPaths")]
+    pub excluded_paths: Vec<String>,
+    pub model: Option<String>,
+    #[serde(alias = "outputTemplate")]
+    pub output_template: Option<String>,
+    pub glossary: Option<PathBuf>,
+    pub format: Option<StyleOverrides>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedProjectConfig {
+    pub path: PathBuf,
+    pub config: ProjectConfig,
+}
+
+// Find and parse the config for the source file at `source`. Returns None if
+// there is no config file next to it.
+pub fn load(source: &Path) -> Result<Option<LoadedProjectConfig>, CommandError> {
+    let dir = source
+        .parent()
+        .ok_or_else(|| CommandError::invalid_input("Source file has no parent directory"))?;
+
+    let path = match CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+    {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let content = fs::read_to_string(&path).map_err(|e| {
+        CommandError::io("Failed to read project config", e).with_details(json!({ "path": path }))
+    })?;
+    let mut config = if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        parse_toml(&content, &path)?
+    } else {
+        serde_json::from_str(&content).map_err(|e| {
+            let (line, column) = (e.line(), e.column());
+            CommandError::json("Invalid project config", e)
+                .with_details(json!({ "path": path, "line": line, "column": column }))
+        })?
+    };
+
+    validate(&config, &path)?;
+    if let Some(glossary) = config.glossary.take() {
+        config.glossary = Some(dir.join(glossary));
+    }
+
+    Ok(Some(LoadedProjectConfig { path, config }))
+}
+
+fn parse_toml(content: &str, path: &Path) -> Result<ProjectConfig, CommandError> {
+    toml::from_str(content).map_err(|e| {
+        let mut details = json!({ "path": path });
+        if let Some(span) = e.span() {
+            let before = &content[..span.start.min(content.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.len() - before.rfind('\n').map_or(0, |index| index + 1) + 1;
+            details["line"] = json!(line);
+            details["column"] = json!(column);
+        }
+        CommandError::invalid_input(format!("Invalid project config: {}", e.message()))
+            .with_details(details)
+    })
+}
+
+// Catch mistakes when the config is loaded rather than halfway through a run
+fn validate(config: &ProjectConfig, path: &Path) -> Result<(), CommandError> {
+    let with_path = |e: CommandError| e.with_details(json!({ "path": path }));
+
+    if let Some(template) = &config.output_template {
+        OutputTemplate::parse(template).map_err(with_path)?;
+    }
+    if let Some(format) = &config.format {
+        JsonStyle::default()
+            .with_overrides(format)
+            .map_err(with_path)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use crate::json_style::{IndentRule, LineEnding};
+
+    fn write_config(name: &str, file: &str, content: &str) -> PathBuf {
+        let dir = test_dir(name);
+        fs::write(dir.join(file), content).unwrap();
+        dir.join("en_gb.json")
+    }
+
+    #[test]
+    fn toml_configs_load_every_setting() {
+        let source = write_config(
+            "config-toml",
+            "localekit.toml",
+            r#"source_locale = "en_gb"
+target_languages = ["de_de", "fr_fr"]
+excluded_paths = ["meta.version"]
+model = "gpt-4o-mini"
+output_template = "{dir}/{language}/{basename}"
+glossary = "docs/glossary.csv"
+
+[format]
+indent = 4
+line_ending = "crlf"
+trailing_newline = true
+"#,
+        );
+
+        let loaded = load(&source).unwrap().unwrap();
+        let dir = source.parent().unwrap();
+        assert_eq!(loaded.path, dir.join("localekit.toml"));
+
+        let config = loaded.config;
+        assert_eq!(config.source_locale.as_deref(), Some("en_gb"));
+        assert_eq!(config.target_languages, ["de_de", "fr_fr"]);
+        assert_eq!(config.excluded_paths, ["meta.version"]);
+        assert_eq!(config.model.as_deref(), Some("gpt-4o-mini"));
+        assert_eq!(
+            config.output_template.as_deref(),
+            Some("{dir}/{language}/{basename}")
+        );
+        assert_eq!(config.glossary, Some(dir.join("docs/glossary.csv")));
+
+        let format = config.format.unwrap();
+        assert!(matches!(format.indent, Some(IndentRule::Spaces(4))));
+        assert_eq!(format.line_ending, Some(LineEnding::CrLf));
+        assert_eq!(format.trailing_newline, Some(true));
+        assert_eq!(format.escape_unicode, None);
+    }
+
+    #[test]
+    fn json_configs_accept_camel_case_and_missing_configs_are_none() {
+        let source = write_config(
+            "config-json",
+            ".localekit.json",
+            r#"{ "sourceLocale": "en_us", "targetLanguages": ["ja_jp"] }"#,
+        );
+        let config = load(&source).unwrap().unwrap().config;
+        assert_eq!(config.source_locale.as_deref(), Some("en_us"));
+        assert_eq!(config.target_languages, ["ja_jp"]);
+        assert!(config.excluded_paths.is_empty());
+
+        let empty = test_dir("config-none");
+        assert!(load(&empty.join("en_gb.json")).unwrap().is_none());
+    }
+
+    #[test]
+    fn invalid_configs_report_where_they_fail() {
+        let source = write_config(
+            "config-unknown-key",
+            "localekit.toml",
+            "source_locale = \"en_gb\"\ntarget_langs = [\"de_de\"]\n",
+        );
+        let error = load(&source).unwrap_err();
+        assert!(error.message.contains("target_langs"), "{}", error.message);
+        let details = error.details.unwrap();
+        assert_eq!(details["line"], 2);
+        assert_eq!(details["column"], 1);
+
+        let source = write_config(
+            "config-bad-template",
+            "localekit.toml",
+            "output_template = \"{dir}/{basename}{ext}\"\n",
+        );
+        let error = load(&source).unwrap_err();
+        assert!(error.message.starts_with("Invalid output template"));
+    }
+}
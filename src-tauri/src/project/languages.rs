@@ -0,0 +1,156 @@
+// Matching directory and file names against the app's language codes
+//
+// The frontend's language list uses lowercase `language_region` codes (`de_de`,
+// `pt_br`), but repositories name locales in many other ways. A name is
+// normalized first (`de-DE` and `de_DE` become `de_de`); a bare language
+// (`de`) maps to the code whose region repeats the language (`de_de`), or else
+// to the first code for that language in the list (`en` → `en_gb`).
+
+pub struct LanguageMatcher {
+    codes: Vec<String>,
+}
+
+impl LanguageMatcher {
+    pub fn new(codes: &[String]) -> Self {
+        Self {
+            codes: codes.iter().map(|code| normalize(code)).collect(),
+        }
+    }
+
+    // The language code `name` refers to, if any
+    pub fn match_name(&self, name: &str) -> Option<String> {
+        let name = normalize(name);
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return None;
+        }
+
+        if let Some(code) = self.codes.iter().find(|code| **code == name) {
+            return Some(code.clone());
+        }
+
+        let is_bare_language =
+            (2..=3).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphabetic());
+        if !is_bare_language {
+            return None;
+        }
+
+        let prefix = format!("{}_", name);
+        self.codes
+            .iter()
+            .find(|code| **code == format!("{}{}", prefix, name))
+            .or_else(|| self.codes.iter().find(|code| code.starts_with(&prefix)))
+            .cloned()
+    }
+
+    // The language of a file stem such as `de_de`, `messages.de` or
+    // `app_pt-BR`, with whatever comes before the language
+    pub fn match_stem(&self, stem: &str) -> Option<(String, Option<String>)> {
+        if let Some(code) = self.match_name(stem) {
+            return Some((code, None));
+        }
+
+        // Try the longest suffix first, so `messages_de_de` is `de_de` rather than `de`
+        stem.char_indices()
+            .filter(|(_, c)| matches!(c, '.' | '_' | '-'))
+            .find_map(|(index, _)| {
+                let (prefix, suffix) = (&stem[..index], &stem[index + 1..]);
+                if prefix.is_empty() {
+                    return None;
+                }
+                self.match_name(suffix)
+                    .map(|code| (code, Some(prefix.to_string())))
+            })
+    }
+}
+
+pub fn normalize(name: &str) -> String {
+    name.trim().to_ascii_lowercase().replace('-', "_")
+}
+
+// How a repository spells locale codes, e.g. in folder names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeStyle {
+    // de
+    Language,
+    // de_de
+    Underscore,
+    // de_DE
+    UnderscoreRegion,
+    // de-DE (BCP 47)
+    Hyphen,
+    // de-de
+    HyphenLower,
+    // pt-rBR, b+zh+Hant (Android resource qualifiers)
+    Android,
+    // de for de_de, else pt_BR: the region only where it doesn't repeat the
+    // language, the inverse of LanguageMatcher's bare languages
+    Short,
+}
+
+impl CodeStyle {
+    pub fn detect(name: &str) -> Self {
+        let has_upper_region = name
+            .split(['-', '_'])
+            .nth(1)
+            .is_some_and(|region| region.chars().any(|c| c.is_ascii_uppercase()));
+
+        if name.contains('-') {
+            if has_upper_region {
+                Self::Hyphen
+            } else {
+                Self::HyphenLower
+            }
+        } else if name.contains('_') {
+            if has_upper_region {
+                Self::UnderscoreRegion
+            } else {
+                Self::Underscore
+            }
+        } else {
+            Self::Language
+        }
+    }
+
+    // Spell an app language code (`de_de`) in this style
+    pub fn format(self, code: &str) -> String {
+        let code = normalize(code);
+        let (language, region) = match code.split_once('_') {
+            Some((language, region)) => (language, Some(region)),
+            None => (code.as_str(), None),
+        };
+        let region = match region {
+            Some(region) => region,
+            None => return language.to_string(),
+        };
+
+        match self {
+            Self::Language => language.to_string(),
+            Self::Underscore => format!("{}_{}", language, region),
+            Self::HyphenLower => format!("{}-{}", language, region),
+            Self::UnderscoreRegion => format!("{}_{}", language, region_case(region)),
+            Self::Hyphen => format!("{}-{}", language, region_case(region)),
+            // Only two-letter regions have the short form
+            Self::Android
+                if region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()) =>
+            {
+                format!("{}-r{}", language, region.to_ascii_uppercase())
+            }
+            Self::Android => format!("b+{}+{}", language, region_case(region)),
+            Self::Short if region == language => language.to_string(),
+            Self::Short => format!("{}_{}", language, region_case(region)),
+        }
+    }
+}
+
+// Regions are upper case (`BR`), scripts title case (`Hant`)
+fn region_case(region: &str) -> String {
+    if region.len() == 4 {
+        let mut chars = region.chars();
+        chars
+            .next()
+            .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    } else {
+        region.to_ascii_uppercase()
+    }
+}
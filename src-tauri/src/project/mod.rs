@@ -0,0 +1,15 @@
+// Multi-file locale projects
+//
+// A project is a directory holding locale files in one of the layouts common
+// in web and mobile repositories. `scan` discovers the files and the language
+// of each, matched against the app's language list by `languages`,
+// `namespaces` handles folder-per-locale projects split into namespaces,
+// `template` decides where translated files are written and `config` reads
+// the settings a repository pins in `localekit.toml`.
+pub mod config;
+pub mod languages;
+pub mod namespaces;
+pub mod scan;
+pub mod template;
+
+pub use languages::LanguageMatcher;
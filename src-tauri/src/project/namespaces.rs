@@ -0,0 +1,171 @@
+// Namespaced projects: one folder per locale, one file per namespace
+//
+//   locales/en/common.json    locales/de/common.json
+//   locales/en/auth.json      locales/de/auth.json
+//
+// The project is the base directory (`locales`) seen as languages ×
+// namespaces. The namespaces are the locale files in the source language's
+// folder. A target language uses its existing folder if there is one;
+// otherwise a folder is named in the same style as the source folder
+// (`en` → `de`, `en-US` → `de-DE`) and created on the first write.
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::languages::CodeStyle;
+use super::scan::is_locale_file;
+use super::LanguageMatcher;
+use crate::encoding;
+use crate::error::CommandError;
+use crate::formats::{self, Format};
+use crate::locale_json::LoadedLocaleFile;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceProject {
+    pub base: PathBuf,
+    pub source_language: String,
+    // Namespace name → file in the source language's folder
+    pub namespaces: BTreeMap<String, PathBuf>,
+    // Language code → existing folder, including the source language
+    pub languages: BTreeMap<String, PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedNamespaces {
+    pub project: NamespaceProject,
+    // Namespace name → parsed source file
+    pub files: BTreeMap<String, LoadedLocaleFile>,
+}
+
+// Identifies one namespace file of one target language
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamespaceTarget {
+    pub base: String,
+    pub source_language: String,
+    pub language: String,
+    pub namespace: String,
+}
+
+impl NamespaceProject {
+    pub fn open(
+        base: &Path,
+        languages: &LanguageMatcher,
+        source_language: &str,
+    ) -> Result<Self, CommandError> {
+        let source_language = languages.match_name(source_language).ok_or_else(|| {
+            CommandError::invalid_input(format!("Unknown source language: {}", source_language))
+        })?;
+
+        let entries = fs::read_dir(base)
+            .map_err(|e| CommandError::io("Failed to read project directory", e))?;
+        let mut language_dirs = BTreeMap::new();
+        for entry in entries {
+            let entry =
+                entry.map_err(|e| CommandError::io("Failed to read project directory", e))?;
+            if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                continue;
+            }
+            let code = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| languages.match_name(name));
+            if let Some(code) = code {
+                language_dirs.insert(code, entry.path());
+            }
+        }
+
+        let source_dir = language_dirs.get(&source_language).ok_or_else(|| {
+            CommandError::not_found(format!(
+                "No folder for the source language {} in '{}'",
+                source_language,
+                base.display()
+            ))
+        })?;
+
+        let mut namespaces = BTreeMap::new();
+        let entries = fs::read_dir(source_dir)
+            .map_err(|e| CommandError::io("Failed to read source language folder", e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| CommandError::io("Failed to read source language folder", e))?
+                .path();
+            if !path.is_file() || !is_locale_file(&path) {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                namespaces.insert(stem.to_string(), path);
+            }
+        }
+
+        Ok(Self {
+            base: base.to_path_buf(),
+            source_language,
+            namespaces,
+            languages: language_dirs,
+        })
+    }
+
+    // Source file of a namespace
+    pub fn source_file(&self, namespace: &str) -> Result<&Path, CommandError> {
+        self.namespaces
+            .get(namespace)
+            .map(PathBuf::as_path)
+            .ok_or_else(|| CommandError::not_found(format!("Unknown namespace: {}", namespace)))
+    }
+
+    // Where the `language` version of `namespace` lives, whether or not it
+    // exists yet
+    pub fn target_file(
+        &self,
+        languages: &LanguageMatcher,
+        language: &str,
+        namespace: &str,
+    ) -> Result<PathBuf, CommandError> {
+        let source_file = self.source_file(namespace)?;
+        let language = languages.match_name(language).ok_or_else(|| {
+            CommandError::invalid_input(format!("Unknown language: {}", language))
+        })?;
+        if language == self.source_language {
+            return Err(CommandError::invalid_input(
+                "The target language is the source language",
+            ));
+        }
+
+        let directory = match self.languages.get(&language) {
+            Some(directory) => directory.clone(),
+            None => {
+                let source_folder = self.languages[&self.source_language]
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default();
+                self.base
+                    .join(CodeStyle::detect(source_folder).format(&language))
+            }
+        };
+
+        // Keep the source file's name, extension included
+        let file_name = source_file
+            .file_name()
+            .ok_or_else(|| CommandError::internal("Namespace file has no name"))?;
+        Ok(directory.join(file_name))
+    }
+}
+
+// Parse every namespace of the source language
+pub fn load_source(project: NamespaceProject) -> Result<LoadedNamespaces, CommandError> {
+    let mut files = BTreeMap::new();
+    for (namespace, path) in &project.namespaces {
+        let text = encoding::read_text(path)
+            .map_err(|e| e.with_details(json!({ "path": path, "namespace": namespace })))?;
+        let loaded = formats::load(Format::from_path(path), &text.content, text.encoding)
+            .map_err(|e| e.with_details(json!({ "path": path, "namespace": namespace })))?;
+        files.insert(namespace.clone(), loaded);
+    }
+
+    Ok(LoadedNamespaces { project, files })
+}
@@ -0,0 +1,376 @@
+// Discovering the locale files in a project directory
+//
+// The directory is walked the way git sees it (hidden entries and anything
+// matched by .gitignore are skipped) and each locale file is classified by
+// where its language appears:
+//
+//   messages/de_de.json, app.de.json   one file per locale (`filePerLocale`)
+//   locales/de/common.json             one folder per locale (`folderPerLocale`),
+//   i18n/de-DE/*.json                  where the file stem is the namespace
+//   res/values-de/strings.xml          (Android and Apple resource folders
+//   de.lproj/Localizable.strings       count as locale folders too)
+//
+// Every format in formats/ is picked up, by extension, except Android XML
+// files other than `values*/strings*.xml` (layouts, colors, the manifest).
+// Files with the same layout and base directory form a group. Each group's
+// languages are split into the source language and the translation targets.
+// Android's unqualified `values/` and Apple's `Base.lproj/` hold the default
+// resources, so when a group has one, it is the source: in the requested
+// source language, else English.
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::LanguageMatcher;
+use crate::error::CommandError;
+use crate::formats;
+
+// Deep enough for `apps/web/src/locales/de/common.json`
+const MAX_DEPTH: usize = 8;
+// Skipped even without a .gitignore entry
+const SKIPPED_DIRS: &[&str] = &["node_modules"];
+// Language of the files in a default resource folder while grouping
+const DEFAULT_FOLDER: &str = "";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Layout {
+    FilePerLocale,
+    FolderPerLocale,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleFile {
+    pub path: PathBuf,
+    // The file stem in folder-per-locale layouts, or what precedes the
+    // language in names like `messages.de.json`
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleGroup {
+    pub layout: Layout,
+    // Directory holding the locale files or locale folders
+    pub base: PathBuf,
+    pub source_language: String,
+    pub source: Vec<LocaleFile>,
+    pub targets: BTreeMap<String, Vec<LocaleFile>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectMap {
+    pub root: PathBuf,
+    pub groups: Vec<LocaleGroup>,
+    // Locale-looking files whose language couldn't be inferred
+    pub unrecognized: Vec<PathBuf>,
+}
+
+pub fn scan(
+    root: &Path,
+    languages: &LanguageMatcher,
+    source_language: Option<&str>,
+) -> Result<ProjectMap, CommandError> {
+    if !root.is_dir() {
+        return Err(CommandError::invalid_input(format!(
+            "'{}' is not a directory",
+            root.display()
+        )));
+    }
+
+    let mut grouped: BTreeMap<(Layout, PathBuf), BTreeMap<String, Vec<LocaleFile>>> =
+        BTreeMap::new();
+    let mut unrecognized = Vec::new();
+
+    let walker = WalkBuilder::new(root)
+        .hidden(true)
+        .git_ignore(true)
+        .require_git(false)
+        .max_depth(Some(MAX_DEPTH))
+        .filter_entry(|entry| {
+            !entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| SKIPPED_DIRS.contains(&name))
+        })
+        .build();
+
+    for entry in walker {
+        // Unreadable entries are skipped rather than failing the whole scan
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            continue;
+        }
+
+        let path = entry.into_path();
+        if !is_locale_file(&path) {
+            continue;
+        }
+
+        match classify(&path, languages) {
+            Some((layout, base, language, file)) => grouped
+                .entry((layout, base))
+                .or_default()
+                .entry(language)
+                .or_default()
+                .push(file),
+            None => unrecognized.push(path),
+        }
+    }
+
+    let source_language = source_language.and_then(|code| languages.match_name(code));
+    let groups = grouped
+        .into_iter()
+        .map(|((layout, base), mut locales)| {
+            let (source_language, source) = match locales.remove(DEFAULT_FOLDER) {
+                Some(mut source) => {
+                    let language = source_language
+                        .clone()
+                        .or_else(|| languages.match_name("en"))
+                        .unwrap_or_else(|| "en".to_string());
+                    // A folder for the same language holds more source files
+                    source.extend(locales.remove(&language).unwrap_or_default());
+                    (language, source)
+                }
+                None => {
+                    let language = pick_source_language(&locales, source_language.as_deref());
+                    let source = locales.remove(&language).unwrap_or_default();
+                    (language, source)
+                }
+            };
+            LocaleGroup {
+                layout,
+                base,
+                source_language,
+                source,
+                targets: locales,
+            }
+        })
+        .collect();
+
+    unrecognized.sort();
+    Ok(ProjectMap {
+        root: root.to_path_buf(),
+        groups,
+        unrecognized,
+    })
+}
+
+// Whether `path` has the extension of a format in formats/
+pub fn has_locale_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            formats::DIALOG_FILTERS
+                .iter()
+                .flat_map(|(_, extensions)| extensions.iter())
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+}
+
+// Whether `path` is a file the scan treats as a locale file
+pub fn is_locale_file(path: &Path) -> bool {
+    if !has_locale_extension(path) {
+        return false;
+    }
+
+    let is_xml = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("xml"));
+    if !is_xml {
+        return true;
+    }
+
+    // Android only keeps translatable strings in `values*/strings*.xml`
+    let folder = path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .and_then(|name| name.to_str());
+    let name = path.file_name().and_then(|name| name.to_str());
+    folder.is_some_and(|folder| folder.starts_with("values"))
+        && name.is_some_and(|name| name.starts_with("strings"))
+}
+
+// The layout and base directory of the group the scan would put `path` in
+pub fn locale_group(path: &Path, languages: &LanguageMatcher) -> Option<(Layout, PathBuf)> {
+    classify(path, languages).map(|(layout, base, _, _)| (layout, base))
+}
+
+// Layout, base directory, language and file entry of a locale file. Files in
+// a default resource folder get the `DEFAULT_FOLDER` language.
+fn classify(
+    path: &Path,
+    languages: &LanguageMatcher,
+) -> Option<(Layout, PathBuf, String, LocaleFile)> {
+    let stem = path.file_stem()?.to_str()?;
+    let parent = path.parent()?;
+
+    // locales/de/common.json
+    let folder_language = parent
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| folder_language(name, languages));
+    if let (Some(language), Some(base)) = (folder_language, parent.parent()) {
+        let file = LocaleFile {
+            path: path.to_path_buf(),
+            namespace: Some(stem.to_string()),
+        };
+        return Some((Layout::FolderPerLocale, base.to_path_buf(), language, file));
+    }
+
+    // messages/de_de.json, messages/app.de.json
+    let (language, prefix) = languages.match_stem(stem)?;
+    let file = LocaleFile {
+        path: path.to_path_buf(),
+        namespace: prefix,
+    };
+    Some((Layout::FilePerLocale, parent.to_path_buf(), language, file))
+}
+
+// The language of a locale folder. Apple folders are named like `de.lproj`,
+// Android ones like `values-de` or `values-pt-rBR`; `values` and
+// `Base.lproj` are the default folders.
+fn folder_language(name: &str, languages: &LanguageMatcher) -> Option<String> {
+    if name == "values" || name.eq_ignore_ascii_case("base.lproj") {
+        return Some(DEFAULT_FOLDER.to_string());
+    }
+
+    let name = name.strip_suffix(".lproj").unwrap_or(name);
+    match name.strip_prefix("values-") {
+        Some(qualifier) => languages.match_name(&qualifier.replacen("-r", "-", 1)),
+        None => languages.match_name(name),
+    }
+}
+
+// The requested source language if the group has it, else English, else the
+// language with the most files (the first one on a tie)
+fn pick_source_language(
+    locales: &BTreeMap<String, Vec<LocaleFile>>,
+    requested: Option<&str>,
+) -> String {
+    if let Some(requested) = requested.filter(|code| locales.contains_key(*code)) {
+        return requested.to_string();
+    }
+    if let Some(english) = locales
+        .keys()
+        .find(|code| code.starts_with("en_") || *code == "en")
+    {
+        return english.clone();
+    }
+
+    locales
+        .iter()
+        .rev()
+        .max_by_key(|(_, files)| files.len())
+        .map(|(code, _)| code.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use std::fs;
+
+    #[test]
+    fn scans_every_supported_format() {
+        let root = test_dir("scan-formats");
+        for file in [
+            "po/de.po",
+            "xliff/messages.de.xlf",
+            "l10n/app_de.arb",
+            "res/values/strings.xml",
+            "res/values-de/strings.xml",
+            "res/values-de/colors.xml",
+            "res/values-pt-rBR/strings.xml",
+            "res/layout/main.xml",
+            "AndroidManifest.xml",
+            "ios/Base.lproj/Localizable.strings",
+            "ios/de.lproj/Localizable.strings",
+            "notes/readme.txt",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "").unwrap();
+        }
+
+        let languages = LanguageMatcher::new(&[
+            "en_gb".to_string(),
+            "de_de".to_string(),
+            "pt_br".to_string(),
+        ]);
+        let map = scan(&root, &languages, None).unwrap();
+        let mut found = Vec::new();
+        for group in &map.groups {
+            let source = (&group.source_language, &group.source);
+            for (language, files) in group.targets.iter().chain(std::iter::once(source)) {
+                for file in files {
+                    let path = file.path.strip_prefix(&root).unwrap();
+                    found.push((group.layout, language.clone(), path.display().to_string()));
+                }
+            }
+        }
+        found.sort();
+
+        assert_eq!(
+            found,
+            vec![
+                (
+                    Layout::FilePerLocale,
+                    "de_de".into(),
+                    "l10n/app_de.arb".into()
+                ),
+                (Layout::FilePerLocale, "de_de".into(), "po/de.po".into()),
+                (
+                    Layout::FilePerLocale,
+                    "de_de".into(),
+                    "xliff/messages.de.xlf".into()
+                ),
+                (
+                    Layout::FolderPerLocale,
+                    "de_de".into(),
+                    "ios/de.lproj/Localizable.strings".into()
+                ),
+                (
+                    Layout::FolderPerLocale,
+                    "de_de".into(),
+                    "res/values-de/strings.xml".into()
+                ),
+                (
+                    Layout::FolderPerLocale,
+                    "en_gb".into(),
+                    "ios/Base.lproj/Localizable.strings".into()
+                ),
+                (
+                    Layout::FolderPerLocale,
+                    "en_gb".into(),
+                    "res/values/strings.xml".into()
+                ),
+                (
+                    Layout::FolderPerLocale,
+                    "pt_br".into(),
+                    "res/values-pt-rBR/strings.xml".into()
+                ),
+            ]
+        );
+        assert!(map.unrecognized.is_empty());
+
+        let android = map
+            .groups
+            .iter()
+            .find(|group| group.base == root.join("res"))
+            .unwrap();
+        assert_eq!(android.source_language, "en_gb");
+        assert_eq!(android.source[0].path, root.join("res/values/strings.xml"));
+    }
+}
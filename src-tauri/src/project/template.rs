@@ -0,0 +1,430 @@
+// Output path templates for translated files
+//
+// A template describes where the file for a target language goes, relative
+// to the source file:
+//
+//   {dir}/{lang}{ext}                   messages/de_de.json (the default,
+//                                       see formats::default_template)
+//   {dir}/{language}/{basename}         locales/de/common.json
+//   {dir}/{basename}.{lang-bcp47}.json  i18n/messages.de-DE.json
+//   {dir}/../{lang_underscore}.json     values/../de_DE.json
+//   {dir}/../values-{lang-android}/{filename}
+//                                       res/values-pt-rBR/strings.xml
+//
+// Placeholders:
+//   {dir}              directory of the source file
+//   {basename}         source file name without its extension
+//   {filename}         source file name with its extension
+//   {ext}              source extension with its dot, e.g. `.json` (`.po`
+//                      for a `.pot` template)
+//   {lang}             app language code, e.g. `de_de`
+//   {lang-bcp47}       BCP 47 tag, e.g. `de-DE`
+//   {lang_underscore}  e.g. `de_DE`
+//   {lang-android}     Android resource qualifier, e.g. `pt-rBR`, `b+zh+Hant`
+//   {lang-short}       `de` for `de_de`, but `pt_BR` for `pt_br`
+//   {language}         language only, e.g. `de`
+//   {region}           region only, e.g. `DE` (empty if the code has none)
+//
+// Any placeholder takes a case transform, e.g. `{language|upper}` → `DE`.
+// When the result has no extension, the source's is appended, so
+// `{dir}/{language}/{basename}` keeps `.json`. A template must mention the
+// language and must never resolve onto the source file.
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use super::languages::{normalize, CodeStyle};
+use crate::error::CommandError;
+use crate::formats;
+use crate::scope;
+
+pub const DEFAULT_TEMPLATE: &str = "{dir}/{lang}{ext}";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputPreview {
+    pub language: String,
+    pub path: Option<PathBuf>,
+    pub exists: bool,
+    // Whether the path is inside a granted project, i.e. can be written
+    pub in_scope: bool,
+    pub error: Option<CommandError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Dir,
+    Basename,
+    Filename,
+    Ext,
+    Lang,
+    LangBcp47,
+    LangUnderscore,
+    LangAndroid,
+    LangShort,
+    Language,
+    Region,
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "dir" => Self::Dir,
+            "basename" => Self::Basename,
+            "filename" => Self::Filename,
+            "ext" => Self::Ext,
+            "lang" => Self::Lang,
+            "lang-bcp47" => Self::LangBcp47,
+            "lang_underscore" => Self::LangUnderscore,
+            "lang-android" => Self::LangAndroid,
+            "lang-short" => Self::LangShort,
+            "language" => Self::Language,
+            "region" => Self::Region,
+            _ => return None,
+        })
+    }
+
+    fn is_language(self) -> bool {
+        matches!(
+            self,
+            Self::Lang
+                | Self::LangBcp47
+                | Self::LangUnderscore
+                | Self::LangAndroid
+                | Self::LangShort
+                | Self::Language
+                | Self::Region
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transform {
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder, Option<Transform>),
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    parts: Vec<Part>,
+}
+
+impl OutputTemplate {
+    pub fn parse(template: &str) -> Result<Self, CommandError> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| template_error(template, "Unclosed '{'"))?;
+
+            let inner = &rest[start + 1..end];
+            let (name, transform) = match inner.split_once('|') {
+                Some((name, transform)) => (name, Some(transform)),
+                None => (inner, None),
+            };
+            let placeholder = Placeholder::parse(name.trim()).ok_or_else(|| {
+                template_error(template, &format!("Unknown placeholder '{{{}}}'", name))
+            })?;
+            let transform = match transform.map(str::trim) {
+                None => None,
+                Some("upper") => Some(Transform::Upper),
+                Some("lower") => Some(Transform::Lower),
+                Some(other) => {
+                    return Err(template_error(
+                        template,
+                        &format!("Unknown transform '{}'", other),
+                    ))
+                }
+            };
+            parts.push(Part::Placeholder(placeholder, transform));
+
+            rest = &rest[end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(template_error(template, "Unmatched '}'"));
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        let mentions_language = parts.iter().any(
+            |part| matches!(part, Part::Placeholder(placeholder, _) if placeholder.is_language()),
+        );
+        if !mentions_language {
+            return Err(template_error(
+                template,
+                "The template must contain a language placeholder",
+            ));
+        }
+
+        Ok(Self { parts })
+    }
+
+    // Path of the `language` file for `source`
+    pub fn resolve(&self, source: &Path, language: &str) -> Result<PathBuf, CommandError> {
+        let language = normalize(language);
+        if language.is_empty()
+            || !language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(CommandError::invalid_input(format!(
+                "Invalid language code: {}",
+                language
+            )));
+        }
+
+        let dir = source
+            .parent()
+            .ok_or_else(|| CommandError::invalid_input("Source file has no parent directory"))?;
+        let filename = source
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| CommandError::invalid_input("Source file has no name"))?;
+        let basename = source
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(filename);
+        let ext = source
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| formats::output_extension(&format!(".{}", ext)).to_string())
+            .unwrap_or_default();
+
+        let mut resolved = String::new();
+        for part in &self.parts {
+            let (placeholder, transform) = match part {
+                Part::Literal(text) => {
+                    resolved.push_str(text);
+                    continue;
+                }
+                Part::Placeholder(placeholder, transform) => (*placeholder, *transform),
+            };
+
+            let value = match placeholder {
+                Placeholder::Dir => dir.to_string_lossy().into_owned(),
+                Placeholder::Basename => basename.to_string(),
+                Placeholder::Filename => filename.to_string(),
+                Placeholder::Ext => ext.clone(),
+                Placeholder::Lang => language.clone(),
+                Placeholder::LangBcp47 => CodeStyle::Hyphen.format(&language),
+                Placeholder::LangUnderscore => CodeStyle::UnderscoreRegion.format(&language),
+                Placeholder::LangAndroid => CodeStyle::Android.format(&language),
+                Placeholder::LangShort => CodeStyle::Short.format(&language),
+                Placeholder::Language => CodeStyle::Language.format(&language),
+                Placeholder::Region => CodeStyle::Hyphen
+                    .format(&language)
+                    .split_once('-')
+                    .map(|(_, region)| region.to_string())
+                    .unwrap_or_default(),
+            };
+            resolved.push_str(&match transform {
+                None => value,
+                Some(Transform::Upper) => value.to_uppercase(),
+                Some(Transform::Lower) => value.to_lowercase(),
+            });
+        }
+
+        let mut path = PathBuf::from(resolved);
+        if path.is_relative() {
+            path = dir.join(path);
+        }
+        let mut path = normalize_lexically(&path);
+        if path.extension().is_none() && !ext.is_empty() {
+            let mut name = path.file_name().unwrap_or_default().to_os_string();
+            name.push(&ext);
+            path.set_file_name(name);
+        }
+
+        if is_same_file(&path, source) {
+            return Err(CommandError::invalid_input(format!(
+                "The output path for {} is the source file",
+                language
+            )));
+        }
+
+        Ok(path)
+    }
+
+    // Resolve the template for every language, flagging languages that would
+    // share an output file
+    pub fn preview(
+        &self,
+        source: &Path,
+        languages: &[String],
+        in_scope: impl Fn(&Path) -> bool,
+    ) -> Vec<OutputPreview> {
+        let mut previews: Vec<OutputPreview> = Vec::with_capacity(languages.len());
+        let mut seen: HashMap<PathBuf, String> = HashMap::new();
+
+        for language in languages {
+            let preview = match self.resolve(source, language) {
+                Ok(path) => match seen.get(&path) {
+                    Some(other) => OutputPreview {
+                        language: language.clone(),
+                        path: Some(path.clone()),
+                        exists: path.exists(),
+                        in_scope: in_scope(&path),
+                        error: Some(CommandError::invalid_input(format!(
+                            "{} and {} resolve to the same file",
+                            other, language
+                        ))),
+                    },
+                    None => {
+                        seen.insert(path.clone(), language.clone());
+                        OutputPreview {
+                            language: language.clone(),
+                            exists: path.exists(),
+                            in_scope: in_scope(&path),
+                            path: Some(path),
+                            error: None,
+                        }
+                    }
+                },
+                Err(error) => OutputPreview {
+                    language: language.clone(),
+                    path: None,
+                    exists: false,
+                    in_scope: false,
+                    error: Some(error),
+                },
+            };
+            previews.push(preview);
+        }
+
+        previews
+    }
+}
+
+// Resolve `.` and `..` without touching the filesystem
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (scope::normalize(a), scope::normalize(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn template_error(template: &str, reason: &str) -> CommandError {
+    CommandError::invalid_input(format!(
+        "Invalid output template '{}': {}",
+        template, reason
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use std::fs;
+
+    fn resolve(template: &str, source: &Path, language: &str) -> PathBuf {
+        OutputTemplate::parse(template)
+            .unwrap()
+            .resolve(source, language)
+            .unwrap()
+    }
+
+    #[test]
+    fn placeholders_expand_to_the_language_and_source_parts() {
+        let root = test_dir("template-expand");
+        let strings = root.join("res/values/strings.xml");
+        let messages = root.join("locales/en/common.json");
+
+        assert_eq!(
+            resolve(
+                "{dir}/../values-{lang-android}/{filename}",
+                &strings,
+                "pt_br"
+            ),
+            root.join("res/values-pt-rBR/strings.xml")
+        );
+        assert_eq!(
+            resolve("{dir}/../{language}/{basename}", &messages, "de_de"),
+            root.join("locales/de/common.json")
+        );
+        assert_eq!(
+            resolve("{dir}/{basename}.{lang-bcp47}.json", &messages, "pt_br"),
+            root.join("locales/en/common.pt-BR.json")
+        );
+        assert_eq!(
+            resolve(
+                "{dir}/{lang_underscore}_{region|lower}{ext}",
+                &messages,
+                "de_de"
+            ),
+            root.join("locales/en/de_DE_de.json")
+        );
+        assert_eq!(
+            resolve(DEFAULT_TEMPLATE, &root.join("po/messages.pot"), "fr_fr"),
+            root.join("po/fr_fr.po")
+        );
+    }
+
+    #[test]
+    fn templates_without_a_language_are_rejected() {
+        for template in [
+            "{dir}/{basename}.copy{ext}",
+            "{dir}/{lang",
+            "{dir}/{country}",
+        ] {
+            let error = OutputTemplate::parse(template).unwrap_err();
+            assert!(
+                error.message.starts_with("Invalid output template"),
+                "{}",
+                error.message
+            );
+        }
+    }
+
+    #[test]
+    fn templates_resolving_onto_the_source_are_rejected() {
+        let root = test_dir("template-source");
+        let source = root.join("messages/de_de.json");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, "{}").unwrap();
+
+        let template = OutputTemplate::parse("{dir}/./{lang}{ext}").unwrap();
+        let error = template.resolve(&source, "de_de").unwrap_err();
+        assert_eq!(
+            error.message,
+            "The output path for de_de is the source file"
+        );
+        assert_eq!(
+            template.resolve(&source, "fr_fr").unwrap(),
+            root.join("messages/fr_fr.json")
+        );
+
+        let previews =
+            template.preview(&source, &["de_de".to_string(), "fr_fr".to_string()], |_| {
+                true
+            });
+        assert!(previews[0].path.is_none() && previews[0].error.is_some());
+        assert!(previews[1].error.is_none());
+    }
+}
@@ -0,0 +1,269 @@
+// Recently opened source files and project directories
+//
+// The list is kept newest first in `<app_data>/recent.json` and capped at
+// MAX_ENTRIES; opening a path again moves it back to the top. Where the
+// platform has an app menu, the list is mirrored in an "Open Recent" submenu.
+// Picking an entry there emits `open-recent` with the entry, and the frontend
+// opens it as if it had just been picked in a dialog. Grants are persisted
+// separately (see scope.rs), so reopening doesn't need the dialog unless the
+// grant was revoked in the meantime.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tauri::menu::{MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, Wry};
+
+use crate::error::CommandError;
+use crate::fs_util::{now_millis, write_atomic, FileMode};
+
+pub const OPEN_RECENT_EVENT: &str = "open-recent";
+pub const CLEAR_MENU_ID: &str = "recent-clear";
+// Entries use this prefix followed by their path as menu id
+const ENTRY_MENU_PREFIX: &str = "recent:";
+
+const RECENT_FILE: &str = "recent.json";
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecentKind {
+    SourceFile,
+    ProjectDirectory,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentEntry {
+    pub path: PathBuf,
+    pub kind: RecentKind,
+    // Milliseconds since the Unix epoch
+    pub opened_at: u64,
+}
+
+// Managed state caching the list after the first access
+#[derive(Default)]
+pub struct RecentList {
+    entries: Mutex<Option<Vec<RecentEntry>>>,
+    // The "Open Recent" submenu, if the app menu has one
+    menu: Mutex<Option<Submenu<Wry>>>,
+}
+
+impl RecentList {
+    pub fn list(&self, data_dir: &Path) -> Result<Vec<RecentEntry>, CommandError> {
+        let guard = self.load(data_dir)?;
+        Ok(guard.clone().unwrap_or_default())
+    }
+
+    // Move `path` to the top of the list. Returns the updated list.
+    pub fn add(
+        &self,
+        data_dir: &Path,
+        path: PathBuf,
+        kind: RecentKind,
+    ) -> Result<Vec<RecentEntry>, CommandError> {
+        let mut guard = self.load(data_dir)?;
+        let entries = guard.get_or_insert_with(Vec::new);
+        entries.retain(|entry| entry.path != path);
+        entries.insert(
+            0,
+            RecentEntry {
+                path,
+                kind,
+                opened_at: now_millis(),
+            },
+        );
+        entries.truncate(MAX_ENTRIES);
+
+        save(data_dir, entries)?;
+        Ok(entries.clone())
+    }
+
+    pub fn clear(&self, data_dir: &Path) -> Result<(), CommandError> {
+        let mut guard = self.load(data_dir)?;
+        let entries = guard.get_or_insert_with(Vec::new);
+        entries.clear();
+
+        save(data_dir, entries)
+    }
+
+    // Keep `submenu` in sync with the list from now on
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub fn attach_menu(
+        &self,
+        app: &AppHandle,
+        data_dir: &Path,
+        submenu: Submenu<Wry>,
+    ) -> Result<(), CommandError> {
+        *self.lock_menu()? = Some(submenu);
+        self.sync_menu(app, &self.list(data_dir)?)
+    }
+
+    // Rebuild the "Open Recent" submenu. Entries whose path is gone are
+    // shown disabled rather than dropped, e.g. for an unmounted drive.
+    pub fn sync_menu(&self, app: &AppHandle, entries: &[RecentEntry]) -> Result<(), CommandError> {
+        let guard = self.lock_menu()?;
+        let Some(submenu) = guard.as_ref() else {
+            return Ok(());
+        };
+
+        while submenu.remove_at(0).map_err(menu_error)?.is_some() {}
+        for entry in entries {
+            let item = MenuItem::with_id(
+                app,
+                format!("{}{}", ENTRY_MENU_PREFIX, entry.path.display()),
+                entry.path.display().to_string(),
+                entry.path.exists(),
+                None::<&str>,
+            )
+            .map_err(menu_error)?;
+            submenu.append(&item).map_err(menu_error)?;
+        }
+        if !entries.is_empty() {
+            let separator = PredefinedMenuItem::separator(app).map_err(menu_error)?;
+            submenu.append(&separator).map_err(menu_error)?;
+        }
+        let clear_item = MenuItem::with_id(
+            app,
+            CLEAR_MENU_ID,
+            "Clear Menu",
+            !entries.is_empty(),
+            None::<&str>,
+        )
+        .map_err(menu_error)?;
+        submenu.append(&clear_item).map_err(menu_error)
+    }
+
+    // Handle a click in the "Open Recent" submenu. Returns false if the menu
+    // item isn't one of ours.
+    pub fn handle_menu_event(
+        &self,
+        app: &AppHandle,
+        data_dir: &Path,
+        id: &str,
+    ) -> Result<bool, CommandError> {
+        if id == CLEAR_MENU_ID {
+            self.clear(data_dir)?;
+            self.sync_menu(app, &[])?;
+            return Ok(true);
+        }
+
+        let Some(path) = id.strip_prefix(ENTRY_MENU_PREFIX) else {
+            return Ok(false);
+        };
+        let entry = self
+            .list(data_dir)?
+            .into_iter()
+            .find(|entry| entry.path == Path::new(path));
+        if let Some(entry) = entry {
+            app.emit(OPEN_RECENT_EVENT, entry)
+                .map_err(|e| CommandError::internal(format!("Failed to emit event: {}", e)))?;
+        }
+        Ok(true)
+    }
+
+    fn load(
+        &self,
+        data_dir: &Path,
+    ) -> Result<MutexGuard<'_, Option<Vec<RecentEntry>>>, CommandError> {
+        let mut guard = self
+            .entries
+            .lock()
+            .map_err(|_| CommandError::internal("Recent list is poisoned"))?;
+
+        if guard.is_none() {
+            let entries = match fs::read_to_string(data_dir.join(RECENT_FILE)) {
+                Ok(content) => serde_json::from_str(&content)
+                    .map_err(|e| CommandError::json("Failed to parse recent list", e))?,
+                Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(CommandError::io("Failed to read recent list", e)),
+            };
+            *guard = Some(entries);
+        }
+
+        Ok(guard)
+    }
+
+    fn lock_menu(&self) -> Result<MutexGuard<'_, Option<Submenu<Wry>>>, CommandError> {
+        self.menu
+            .lock()
+            .map_err(|_| CommandError::internal("Recent menu is poisoned"))
+    }
+}
+
+fn save(data_dir: &Path, entries: &[RecentEntry]) -> Result<(), CommandError> {
+    let content = serde_json::to_string_pretty(entries)
+        .map_err(|e| CommandError::json("Failed to serialize recent list", e))?;
+
+    fs::create_dir_all(data_dir)
+        .map_err(|e| CommandError::io("Failed to create app data directory", e))?;
+    write_atomic(
+        &data_dir.join(RECENT_FILE),
+        content.as_bytes(),
+        FileMode::Private,
+    )
+    .map_err(|e| CommandError::io("Failed to write recent list", e))
+}
+
+fn menu_error(e: tauri::Error) -> CommandError {
+    CommandError::internal(format!("Failed to update recent menu: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+
+    fn paths(entries: &[RecentEntry]) -> Vec<PathBuf> {
+        entries.iter().map(|entry| entry.path.clone()).collect()
+    }
+
+    #[test]
+    fn reopening_a_path_moves_it_to_the_top() {
+        let data_dir = test_dir("recent-dedupe");
+        let recent = RecentList::default();
+        recent
+            .add(&data_dir, "/a/en.json".into(), RecentKind::SourceFile)
+            .unwrap();
+        recent
+            .add(&data_dir, "/b".into(), RecentKind::ProjectDirectory)
+            .unwrap();
+        let entries = recent
+            .add(&data_dir, "/a/en.json".into(), RecentKind::SourceFile)
+            .unwrap();
+
+        assert_eq!(paths(&entries), [PathBuf::from("/a/en.json"), "/b".into()]);
+        assert_eq!(entries[1].kind, RecentKind::ProjectDirectory);
+
+        // A fresh list reads the same entries back from disk
+        let reloaded = RecentList::default().list(&data_dir).unwrap();
+        assert_eq!(paths(&reloaded), paths(&entries));
+    }
+
+    #[test]
+    fn the_list_keeps_the_newest_entries_up_to_the_cap() {
+        let data_dir = test_dir("recent-cap");
+        let recent = RecentList::default();
+        for index in 0..MAX_ENTRIES + 3 {
+            recent
+                .add(
+                    &data_dir,
+                    format!("/project-{}", index).into(),
+                    RecentKind::ProjectDirectory,
+                )
+                .unwrap();
+        }
+
+        let entries = recent.list(&data_dir).unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(
+            entries[0].path,
+            PathBuf::from(format!("/project-{}", MAX_ENTRIES + 2))
+        );
+        assert_eq!(entries[MAX_ENTRIES - 1].path, PathBuf::from("/project-3"));
+
+        recent.clear(&data_dir).unwrap();
+        assert!(RecentList::default().list(&data_dir).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,491 @@
+// Filesystem scope for the locale file commands
+//
+// read_json_file, write_json_file and check_file_exists only accept paths the
+// user granted through a native dialog. Picking a project directory grants
+// that directory tree for reading. Picking a source file grants the file and
+// the files directly next to it, which is where translated files are written,
+// but not the folders below. For Android and Apple resources, whose
+// translations go into sibling folders (`values-de/strings.xml`,
+// `de.lproj/...`), files with the source's name in the folders next to the
+// source's folder are granted too.
+//
+// Writes are further limited to locale files (see scan::is_locale_file).
+// Inside a directory grant, they are limited to files the project scan would
+// put in one of the locale groups it found, so `package.json` or
+// `.vscode/settings.json` can't be overwritten; nothing can be written there
+// before the directory has been scanned. Grants are grouped by project (the
+// file or directory they came from) and persisted in `<app_data>/scopes.json`,
+// so reopening a project doesn't need the dialog.
+//
+// Paths are canonicalized before they are compared, so `..` segments and
+// symlinks can't be used to step outside a granted directory.
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::error::{CommandError, ErrorCode};
+use crate::formats;
+use crate::fs_util::{now_millis, write_atomic, FileMode};
+use crate::project::scan::{self, is_locale_file, Layout, ProjectMap};
+use crate::project::LanguageMatcher;
+
+const SCOPES_FILE: &str = "scopes.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectScope {
+    source: PathBuf,
+    directories: Vec<PathBuf>,
+    // Milliseconds since the Unix epoch
+    granted_at: u64,
+    // For a directory grant, the locale groups found by its last scan and the
+    // language codes the scan matched names against
+    #[serde(default)]
+    groups: Vec<ScannedGroup>,
+    #[serde(default)]
+    languages: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScannedGroup {
+    layout: Layout,
+    base: PathBuf,
+}
+
+impl ProjectScope {
+    // A directory grant is recorded with the directory as its source
+    fn is_directory(&self) -> bool {
+        self.directories.contains(&self.source)
+    }
+
+    fn allows(&self, path: &Path) -> bool {
+        if self.source == path {
+            return true;
+        }
+        if self.is_directory() {
+            return self.directories.iter().any(|dir| path.starts_with(dir));
+        }
+
+        let parent = path.parent();
+        let in_sibling_folder = uses_sibling_folders(&self.source)
+            && path.file_name() == self.source.file_name()
+            && parent.and_then(Path::parent) == self.source.parent().and_then(Path::parent);
+        in_sibling_folder
+            || self
+                .directories
+                .iter()
+                .any(|dir| parent == Some(dir.as_path()))
+    }
+
+    // Whether `path` may be written. Inside a directory grant that means the
+    // scan would put it in one of the groups it found.
+    fn allows_write(&self, path: &Path) -> bool {
+        if !self.allows(path) {
+            return false;
+        }
+        if !self.is_directory() {
+            return true;
+        }
+
+        let languages = LanguageMatcher::new(&self.languages);
+        scan::locale_group(path, &languages).is_some_and(|(layout, base)| {
+            self.groups
+                .iter()
+                .any(|group| group.layout == layout && group.base == base)
+        })
+    }
+}
+
+// Whether translations of `source` go into folders next to its own, see
+// formats::default_template
+fn uses_sibling_folders(source: &Path) -> bool {
+    formats::default_template(source).starts_with("{dir}/../")
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Grants {
+    // Keyed by the canonical source file path
+    projects: BTreeMap<String, ProjectScope>,
+}
+
+// Managed state caching the grants file after the first access
+#[derive(Default)]
+pub struct ScopeRegistry {
+    grants: Mutex<Option<Grants>>,
+}
+
+impl ScopeRegistry {
+    // Grant a source file picked by the user, plus the files next to it for
+    // outputs
+    pub fn grant_project(&self, data_dir: &Path, source: &Path) -> Result<(), CommandError> {
+        let source = source
+            .canonicalize()
+            .map_err(|e| CommandError::io("Failed to resolve source file", e))?;
+        let directory = source
+            .parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| CommandError::invalid_input("Source file has no parent directory"))?;
+
+        self.grant(data_dir, source, directory)
+    }
+
+    // Grant a project directory picked by the user
+    pub fn grant_directory(&self, data_dir: &Path, directory: &Path) -> Result<(), CommandError> {
+        let directory = directory
+            .canonicalize()
+            .map_err(|e| CommandError::io("Failed to resolve project directory", e))?;
+
+        self.grant(data_dir, directory.clone(), directory)
+    }
+
+    fn grant(
+        &self,
+        data_dir: &Path,
+        source: PathBuf,
+        directory: PathBuf,
+    ) -> Result<(), CommandError> {
+        let mut guard = self.load(data_dir)?;
+        let grants = guard.get_or_insert_with(Grants::default);
+        grants.projects.insert(
+            source.to_string_lossy().into_owned(),
+            ProjectScope {
+                source,
+                directories: vec![directory],
+                granted_at: now_millis(),
+                groups: Vec::new(),
+                languages: Vec::new(),
+            },
+        );
+
+        save(data_dir, grants)
+    }
+
+    // Resolve `path` and make sure it falls inside a granted project.
+    // Returns the canonical path to operate on.
+    pub fn check(&self, data_dir: &Path, path: &str) -> Result<PathBuf, CommandError> {
+        let resolved = normalize(Path::new(path)).ok_or_else(|| outside_scope(path))?;
+
+        let guard = self.load(data_dir)?;
+        let allowed = guard
+            .iter()
+            .flat_map(|grants| grants.projects.values())
+            .any(|project| project.allows(&resolved));
+
+        if allowed {
+            Ok(resolved)
+        } else {
+            Err(outside_scope(path))
+        }
+    }
+
+    // Like `check`, for a path that is about to be written: only locale files
+    // can be, and inside a directory grant only the ones its scan found a
+    // place for
+    pub fn check_write(&self, data_dir: &Path, path: &str) -> Result<PathBuf, CommandError> {
+        let resolved = self.check(data_dir, path)?;
+        if !is_locale_file(&resolved) {
+            return Err(CommandError::new(
+                ErrorCode::PathOutsideScope,
+                format!("'{}' is not a locale file", path),
+            )
+            .with_details(json!({ "path": path })));
+        }
+
+        let guard = self.load(data_dir)?;
+        let writable = guard
+            .iter()
+            .flat_map(|grants| grants.projects.values())
+            .any(|project| project.allows_write(&resolved));
+        if !writable {
+            return Err(CommandError::new(
+                ErrorCode::PathOutsideScope,
+                format!("'{}' is not in a locale folder of the project", path),
+            )
+            .with_details(json!({ "path": path })));
+        }
+
+        Ok(resolved)
+    }
+
+    // Remember the locale groups a scan found, so the directory grant
+    // covering them allows writing their files
+    pub fn record_scan(
+        &self,
+        data_dir: &Path,
+        map: &ProjectMap,
+        languages: &[String],
+    ) -> Result<(), CommandError> {
+        let mut guard = self.load(data_dir)?;
+        let grants = guard.get_or_insert_with(Grants::default);
+        let Some(project) = grants
+            .projects
+            .values_mut()
+            .find(|project| project.is_directory() && project.allows(&map.root))
+        else {
+            return Ok(());
+        };
+
+        project
+            .groups
+            .retain(|group| !group.base.starts_with(&map.root));
+        project
+            .groups
+            .extend(map.groups.iter().map(|group| ScannedGroup {
+                layout: group.layout,
+                base: group.base.clone(),
+            }));
+        project.languages = languages.to_vec();
+
+        save(data_dir, grants)
+    }
+
+    pub fn projects(&self, data_dir: &Path) -> Result<Vec<ProjectScope>, CommandError> {
+        let guard = self.load(data_dir)?;
+        Ok(guard
+            .iter()
+            .flat_map(|grants| grants.projects.values().cloned())
+            .collect())
+    }
+
+    // Forget the grants for one project. Returns whether anything was removed.
+    pub fn revoke(&self, data_dir: &Path, source: &str) -> Result<bool, CommandError> {
+        let mut guard = self.load(data_dir)?;
+        let grants = guard.get_or_insert_with(Grants::default);
+
+        let key = normalize(Path::new(source))
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| source.to_string());
+        if grants.projects.remove(&key).is_none() {
+            return Ok(false);
+        }
+
+        save(data_dir, grants)?;
+        Ok(true)
+    }
+
+    fn load(&self, data_dir: &Path) -> Result<MutexGuard<'_, Option<Grants>>, CommandError> {
+        let mut guard = self
+            .grants
+            .lock()
+            .map_err(|_| CommandError::internal("Scope registry is poisoned"))?;
+
+        if guard.is_none() {
+            let grants = match fs::read_to_string(data_dir.join(SCOPES_FILE)) {
+                Ok(content) => serde_json::from_str(&content)
+                    .map_err(|e| CommandError::json("Failed to parse scopes file", e))?,
+                Err(e) if e.kind() == ErrorKind::NotFound => Grants::default(),
+                Err(e) => return Err(CommandError::io("Failed to read scopes file", e)),
+            };
+            *guard = Some(grants);
+        }
+
+        Ok(guard)
+    }
+}
+
+fn save(data_dir: &Path, grants: &Grants) -> Result<(), CommandError> {
+    let content = serde_json::to_string_pretty(grants)
+        .map_err(|e| CommandError::json("Failed to serialize scopes file", e))?;
+
+    fs::create_dir_all(data_dir)
+        .map_err(|e| CommandError::io("Failed to create app data directory", e))?;
+    write_atomic(
+        &data_dir.join(SCOPES_FILE),
+        content.as_bytes(),
+        FileMode::Private,
+    )
+    .map_err(|e| CommandError::io("Failed to write scopes file", e))
+}
+
+// Canonicalize a path that may not exist yet (e.g. an output file) by
+// canonicalizing its closest existing ancestor and re-appending the rest.
+// Relative paths and `..` in the non-existent part are rejected.
+pub fn normalize(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let mut resolved = canonical;
+            for component in missing.iter().rev() {
+                resolved.push(component);
+            }
+            return Some(resolved);
+        }
+
+        missing.push(existing.file_name()?.to_os_string());
+        existing = existing.parent()?;
+    }
+}
+
+fn outside_scope(path: &str) -> CommandError {
+    CommandError::new(
+        ErrorCode::PathOutsideScope,
+        format!("Access to '{}' has not been granted", path),
+    )
+    .with_details(json!({ "path": path }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+
+    fn touch(path: &Path) -> String {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn file_grants_cover_only_the_files_next_to_the_source() {
+        let dir = test_dir("scope-file").canonicalize().unwrap();
+        let data_dir = dir.join("data");
+        let source = dir.join("project/messages/en.json");
+        touch(&source);
+        let scope = ScopeRegistry::default();
+        scope.grant_project(&data_dir, &source).unwrap();
+
+        let target = dir.join("project/messages/de.json");
+        assert_eq!(scope.check(&data_dir, &touch(&target)).unwrap(), target);
+        // Files that don't exist yet can be written
+        let new_target = dir.join("project/messages/fr.json");
+        assert!(scope
+            .check_write(&data_dir, &new_target.to_string_lossy())
+            .is_ok());
+
+        for outside in [
+            "project/messages/nested/de.json",
+            "project/other.json",
+            "project/values-de/en.json",
+        ] {
+            let error = scope
+                .check(&data_dir, &touch(&dir.join(outside)))
+                .unwrap_err();
+            assert_eq!(error.code, ErrorCode::PathOutsideScope, "{}", outside);
+        }
+    }
+
+    #[test]
+    fn writes_are_limited_to_locale_files() {
+        let dir = test_dir("scope-write").canonicalize().unwrap();
+        let data_dir = dir.join("data");
+        let source = dir.join("project/en.json");
+        touch(&source);
+        let scope = ScopeRegistry::default();
+        scope.grant_project(&data_dir, &source).unwrap();
+
+        let script = dir.join("project/build.sh");
+        assert!(scope.check(&data_dir, &touch(&script)).is_ok());
+        let error = scope
+            .check_write(&data_dir, &script.to_string_lossy())
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::PathOutsideScope);
+
+        for locale_file in ["de.json", "de.po", "de.xlf", "app_de.arb"] {
+            let path = dir.join("project").join(locale_file);
+            assert!(scope
+                .check_write(&data_dir, &path.to_string_lossy())
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn resource_grants_cover_same_named_files_in_sibling_folders() {
+        let dir = test_dir("scope-android").canonicalize().unwrap();
+        let data_dir = dir.join("data");
+        let source = dir.join("res/values/strings.xml");
+        touch(&source);
+        let scope = ScopeRegistry::default();
+        scope.grant_project(&data_dir, &source).unwrap();
+
+        let target = dir.join("res/values-de/strings.xml");
+        assert!(scope
+            .check_write(&data_dir, &target.to_string_lossy())
+            .is_ok());
+
+        let other_name = dir.join("res/values-de/colors.xml");
+        assert!(scope
+            .check(&data_dir, &other_name.to_string_lossy())
+            .is_err());
+        let too_deep = dir.join("res/values-de/nested/strings.xml");
+        assert!(scope.check(&data_dir, &too_deep.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn directory_grants_cover_the_whole_tree() {
+        let dir = test_dir("scope-directory").canonicalize().unwrap();
+        let data_dir = dir.join("data");
+        let project = dir.join("project");
+        fs::create_dir_all(&project).unwrap();
+        let scope = ScopeRegistry::default();
+        scope.grant_directory(&data_dir, &project).unwrap();
+
+        let nested = project.join("locales/de/common.json");
+        assert_eq!(scope.check(&data_dir, &touch(&nested)).unwrap(), nested);
+        assert!(scope.check(&data_dir, &project.to_string_lossy()).is_ok());
+
+        let outside = dir.join("elsewhere/de.json");
+        assert!(scope.check(&data_dir, &touch(&outside)).is_err());
+        // `..` can't step out of the grant
+        let escaped = format!("{}/../elsewhere/de.json", project.display());
+        assert!(scope.check(&data_dir, &escaped).is_err());
+    }
+
+    #[test]
+    fn directory_grants_only_write_files_in_scanned_groups() {
+        let dir = test_dir("scope-directory-write").canonicalize().unwrap();
+        let data_dir = dir.join("data");
+        let project = dir.join("project");
+        touch(&project.join("locales/en/common.json"));
+        touch(&project.join("messages/en.json"));
+        let package = project.join("package.json");
+        touch(&package);
+        let scope = ScopeRegistry::default();
+        scope.grant_directory(&data_dir, &project).unwrap();
+
+        let languages = vec!["en_gb".to_string(), "de_de".to_string()];
+        let target = project.join("locales/de/common.json");
+        // Nothing is writable before the scan
+        assert!(scope
+            .check_write(&data_dir, &target.to_string_lossy())
+            .is_err());
+
+        let map = scan::scan(&project, &LanguageMatcher::new(&languages), None).unwrap();
+        scope.record_scan(&data_dir, &map, &languages).unwrap();
+
+        for writable in [target, project.join("messages/de.json")] {
+            assert!(
+                scope
+                    .check_write(&data_dir, &writable.to_string_lossy())
+                    .is_ok(),
+                "{}",
+                writable.display()
+            );
+        }
+        for refused in [
+            package,
+            project.join("tsconfig.json"),
+            project.join(".vscode/settings.json"),
+            project.join("de.json"),
+        ] {
+            let error = scope
+                .check_write(&data_dir, &refused.to_string_lossy())
+                .unwrap_err();
+            assert_eq!(
+                error.code,
+                ErrorCode::PathOutsideScope,
+                "{}",
+                refused.display()
+            );
+        }
+    }
+}
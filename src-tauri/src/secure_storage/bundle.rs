@@ -0,0 +1,244 @@
+// Encrypted export bundles for moving secrets between machines
+//
+// A bundle is a small JSON envelope around one sealed payload:
+//
+//   { "format": "localekit-bundle", "version": 1,
+//     "salt": "<base64>", "kdf": { memoryKib, iterations, parallelism },
+//     "payload": "<base64 nonce | ciphertext>" }
+//
+// The payload key is derived from the export passphrase with Argon2id, so the
+// bundle doesn't depend on this install's data key, keyring or vault. The
+// decrypted payload holds every secret in plaintext plus the frontend's
+// preferences (custom languages, selected model, ...), which Rust treats as
+// an opaque JSON value.
+//
+// Bundles are untrusted input, so their KDF parameters are checked against
+// fixed limits before Argon2 sees them.
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::crypto::{self, KdfParams, SALT_LEN};
+use super::{metadata, parse_key, SecretStore, Vault};
+use crate::error::{CommandError, ErrorCode};
+use crate::fs_util::{write_atomic, FileMode};
+
+pub const BUNDLE_EXTENSION: &str = "lkbundle";
+const BUNDLE_FORMAT: &str = "localekit-bundle";
+const BUNDLE_VERSION: u32 = 1;
+const BUNDLE_AAD: &[u8] = b"localekit-bundle-v1";
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    version: u32,
+    salt: String,
+    kdf: KdfParams,
+    payload: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    secrets: BTreeMap<String, String>,
+    #[serde(default)]
+    preferences: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    // Bundle values win over existing keys with the same name
+    Overwrite,
+    // Existing keys are left untouched
+    KeepExisting,
+    // Every existing key is removed before importing
+    Replace,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    exported: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    imported: usize,
+    skipped: usize,
+    preferences: Option<serde_json::Value>,
+}
+
+pub fn export(
+    path: &Path,
+    passphrase: &str,
+    preferences: Option<serde_json::Value>,
+    keys_dir: &Path,
+    store: &dyn SecretStore,
+    vault: &Vault,
+) -> Result<ExportSummary, CommandError> {
+    if passphrase.is_empty() {
+        return Err(CommandError::new(
+            ErrorCode::WeakPassphrase,
+            "Export passphrase must not be empty",
+        ));
+    }
+
+    let mut secrets = BTreeMap::new();
+    for key in store.keys()? {
+        let stored = store.get(&key)?;
+        secrets.insert(key.to_string(), vault.open(keys_dir, &key, &stored)?);
+    }
+    let exported = secrets.len();
+
+    let plaintext = serde_json::to_vec(&Payload {
+        secrets,
+        preferences,
+    })
+    .map_err(|e| CommandError::json("Failed to serialize bundle", e))?;
+
+    let salt = crypto::random_bytes::<SALT_LEN>()?;
+    let kdf = KdfParams::default();
+    let bundle_key = crypto::derive_key(passphrase, &salt, kdf)?;
+    let sealed = crypto::encrypt(&bundle_key, BUNDLE_AAD, &plaintext)?;
+
+    let envelope = Envelope {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        salt: general_purpose::STANDARD.encode(salt),
+        kdf,
+        payload: general_purpose::STANDARD.encode(sealed),
+    };
+    let content = serde_json::to_string_pretty(&envelope)
+        .map_err(|e| CommandError::json("Failed to serialize bundle", e))?;
+
+    write_atomic(path, content.as_bytes(), FileMode::Private)
+        .map_err(|e| CommandError::io("Failed to write bundle", e))?;
+
+    Ok(ExportSummary { exported })
+}
+
+pub fn import(
+    path: &Path,
+    passphrase: &str,
+    strategy: MergeStrategy,
+    keys_dir: &Path,
+    store: &dyn SecretStore,
+    vault: &Vault,
+) -> Result<ImportSummary, CommandError> {
+    let content =
+        fs::read_to_string(path).map_err(|e| CommandError::io("Failed to read bundle", e))?;
+    let envelope: Envelope = serde_json::from_str(&content)
+        .map_err(|e| CommandError::json("File is not a LocaleKit bundle", e))?;
+
+    if envelope.format != BUNDLE_FORMAT {
+        return Err(CommandError::invalid_input(
+            "File is not a LocaleKit bundle",
+        ));
+    }
+    if envelope.version != BUNDLE_VERSION {
+        return Err(CommandError::invalid_input(format!(
+            "Unsupported bundle version: {}",
+            envelope.version
+        )));
+    }
+
+    let salt = general_purpose::STANDARD
+        .decode(&envelope.salt)
+        .map_err(|e| CommandError::invalid_input(format!("Failed to decode bundle salt: {}", e)))?;
+    let sealed = general_purpose::STANDARD
+        .decode(&envelope.payload)
+        .map_err(|e| {
+            CommandError::invalid_input(format!("Failed to decode bundle payload: {}", e))
+        })?;
+
+    let bundle_key = crypto::derive_key(passphrase, &salt, envelope.kdf.check_limits()?)?;
+    let plaintext = crypto::decrypt(&bundle_key, BUNDLE_AAD, &sealed).ok_or_else(|| {
+        CommandError::new(
+            ErrorCode::WrongPassphrase,
+            "Wrong passphrase or corrupted bundle",
+        )
+    })?;
+    let payload: Payload = serde_json::from_slice(&plaintext)
+        .map_err(|e| CommandError::json("Failed to parse bundle contents", e))?;
+
+    // Validate and seal everything up front so a bad name or a locked vault
+    // fails before the store is touched
+    let mut secrets = Vec::with_capacity(payload.secrets.len());
+    for (name, value) in payload.secrets {
+        let key = parse_key(&name)?;
+        let stored = vault.seal(keys_dir, &key, &value)?;
+        secrets.push((key, stored));
+    }
+
+    let existing = store.keys()?;
+    if let MergeStrategy::Replace = strategy {
+        for key in &existing {
+            store.remove(key)?;
+            metadata::record_remove(keys_dir, key)?;
+        }
+    }
+
+    let mut imported = 0;
+    let mut skipped = 0;
+    for (key, stored) in secrets {
+        if let MergeStrategy::KeepExisting = strategy {
+            if existing.contains(&key) {
+                skipped += 1;
+                continue;
+            }
+        }
+
+        store.set(&key, &stored)?;
+        metadata::record_write(keys_dir, &key)?;
+        imported += 1;
+    }
+
+    Ok(ImportSummary {
+        imported,
+        skipped,
+        preferences: payload.preferences,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use crate::secure_storage::file::FileStore;
+
+    #[test]
+    fn import_rejects_oversized_kdf_parameters() {
+        let dir = test_dir("bundle-kdf-limits");
+        let path = dir.join("crafted.lkbundle");
+        let envelope = Envelope {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            salt: general_purpose::STANDARD.encode([0u8; SALT_LEN]),
+            kdf: KdfParams {
+                memory_kib: 4 * 1024 * 1024,
+                iterations: 3,
+                parallelism: 1,
+            },
+            payload: general_purpose::STANDARD.encode([0u8; 64]),
+        };
+        fs::write(&path, serde_json::to_string(&envelope).unwrap()).unwrap();
+
+        let store = FileStore::new(&dir, "localekit-test-bundle");
+        let Err(error) = import(
+            &path,
+            "passphrase",
+            MergeStrategy::Overwrite,
+            &dir,
+            &store,
+            &Vault::default(),
+        ) else {
+            panic!("a bundle with oversized KDF parameters was imported");
+        };
+
+        assert_eq!(error.code, ErrorCode::InvalidInput);
+        assert!(error.message.contains("limits"));
+    }
+}
@@ -0,0 +1,135 @@
+// Crypto helpers shared by the file store, the vault and export bundles
+//
+// Sealed payloads are XChaCha20-Poly1305 `nonce (24 bytes) | ciphertext + tag`,
+// with a fresh random nonce per call. Callers bind the key name (or another
+// context string) as associated data. Passphrases are stretched with Argon2id.
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandError;
+
+pub use chacha20poly1305::Key;
+
+pub const NONCE_LEN: usize = 24;
+pub const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+
+// Argon2id cost parameters, stored next to whatever they protect so the
+// defaults can be raised later without breaking existing data
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    // 64 MiB, 3 passes, 1 lane
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    // Largest parameters accepted from outside the app (an imported bundle).
+    // Well above the defaults, but low enough that a crafted file can't make
+    // Argon2 allocate gigabytes or run for minutes.
+    const MAX_MEMORY_KIB: u32 = 256 * 1024;
+    const MAX_ITERATIONS: u32 = 10;
+    const MAX_PARALLELISM: u32 = 4;
+
+    pub fn check_limits(self) -> Result<Self, CommandError> {
+        if self.memory_kib > Self::MAX_MEMORY_KIB
+            || self.iterations > Self::MAX_ITERATIONS
+            || self.parallelism > Self::MAX_PARALLELISM
+        {
+            return Err(CommandError::invalid_input(format!(
+                "Key derivation parameters exceed the supported limits \
+                 ({} KiB, {} passes, {} lanes)",
+                self.memory_kib, self.iterations, self.parallelism
+            )));
+        }
+
+        Ok(self)
+    }
+}
+
+pub fn generate_key() -> Key {
+    XChaCha20Poly1305::generate_key(&mut OsRng)
+}
+
+pub fn key_from_slice(bytes: &[u8]) -> Option<Key> {
+    (bytes.len() == KEY_LEN).then(|| *Key::from_slice(bytes))
+}
+
+pub fn random_bytes<const N: usize>() -> Result<[u8; N], CommandError> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| CommandError::internal(format!("Failed to gather randomness: {}", e)))?;
+    Ok(bytes)
+}
+
+pub fn encrypt(key: &Key, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, CommandError> {
+    let cipher = XChaCha20Poly1305::new(key);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| CommandError::internal("Failed to encrypt value"))?;
+
+    let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(nonce.as_slice());
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+// Returns None when the payload is truncated or fails authentication, which
+// covers both tampering and a wrong key
+pub fn decrypt(key: &Key, aad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    XChaCha20Poly1305::new(key)
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .ok()
+}
+
+pub fn derive_key(passphrase: &str, salt: &[u8], params: KdfParams) -> Result<Key, CommandError> {
+    use argon2::{Algorithm, Argon2, Params, Version};
+
+    let params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(KEY_LEN),
+    )
+    .map_err(|e| CommandError::internal(format!("Invalid key derivation parameters: {}", e)))?;
+
+    let mut derived = [0u8; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut derived)
+        .map_err(|e| CommandError::internal(format!("Failed to derive key: {}", e)))?;
+
+    Ok(*Key::from_slice(&derived))
+}
@@ -0,0 +1,258 @@
+// Encrypted file-based secret store
+//
+// Every secret lives in `<app_data>/.keys/<key>.dat` and is sealed with
+// XChaCha20-Poly1305 under a per-install data key. Sealed files start with a
+// small versioned header:
+//
+//   "LKSS" | version (1 byte) | nonce (24 bytes) | ciphertext + tag
+//
+// The key name is bound as associated data, so a sealed file copied over
+// another key's file fails to open instead of leaking the wrong secret.
+// Files written before encryption at rest (plain base64) are re-sealed the
+// first time they are read.
+//
+// The data key is kept in the OS keyring when one answers (an empty
+// `.keys/data.key.keyring` marks that), so the `.dat` files alone can't be
+// decrypted. A `data.key` file from an older install is moved there on first
+// use. Without a keyring (headless Linux, CI) the data key is stored in
+// `.keys/data.key`, next to the files it protects: that keeps secrets out of
+// anything that copies the `.dat` files without it, but whoever can read the
+// whole `.keys` directory can decrypt them. There, the owner-only permissions
+// of `.keys` are the actual protection.
+use base64::{engine::general_purpose, Engine as _};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::crypto::{self, Key, NONCE_LEN};
+use super::os_keyring::KeyringStore;
+use super::{Backend, SecretStore, StorageKey};
+use crate::error::CommandError;
+use crate::fs_util::{create_atomic, write_atomic, FileMode};
+
+const MAGIC: &[u8; 4] = b"LKSS";
+const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + NONCE_LEN;
+const DATA_KEY_FILE: &str = "data.key";
+const DATA_KEY_MARKER: &str = "data.key.keyring";
+const DATA_KEY_ENTRY: &str = "__localekit_data_key__";
+
+fn key_file_path(keys_dir: &Path, key: &StorageKey) -> PathBuf {
+    keys_dir.join(format!("{}.dat", key))
+}
+
+fn is_sealed(contents: &[u8]) -> bool {
+    contents.len() >= HEADER_LEN && contents.starts_with(MAGIC)
+}
+
+fn seal(data_key: &Key, key: &StorageKey, value: &str) -> Result<Vec<u8>, CommandError> {
+    let payload = crypto::encrypt(data_key, key.as_str().as_bytes(), value.as_bytes())?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + 1 + payload.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.push(FORMAT_VERSION);
+    sealed.extend_from_slice(&payload);
+    Ok(sealed)
+}
+
+fn open(data_key: &Key, key: &StorageKey, contents: &[u8]) -> Result<String, CommandError> {
+    let version = contents[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(CommandError::internal(format!(
+            "Unsupported key file version: {}",
+            version
+        )));
+    }
+
+    let plaintext = crypto::decrypt(
+        data_key,
+        key.as_str().as_bytes(),
+        &contents[MAGIC.len() + 1..],
+    )
+    .ok_or_else(|| CommandError::internal(format!("Failed to decrypt key '{}'", key)))?;
+
+    String::from_utf8(plaintext)
+        .map_err(|e| CommandError::internal(format!("Failed to decode value: {}", e)))
+}
+
+// Decode a pre-encryption `.dat` file, which holds the value as plain base64
+fn decode_legacy(contents: &[u8]) -> Result<String, CommandError> {
+    let decoded_bytes = general_purpose::STANDARD
+        .decode(contents.trim_ascii())
+        .map_err(|e| CommandError::internal(format!("Failed to decode base64: {}", e)))?;
+
+    String::from_utf8(decoded_bytes)
+        .map_err(|e| CommandError::internal(format!("Failed to decode value: {}", e)))
+}
+
+pub struct FileStore {
+    keys_dir: PathBuf,
+    // Holds the data key when a keyring is available
+    keyring: KeyringStore,
+    // Loaded on first use, so the keyring isn't asked on every access
+    data_key: Mutex<Option<Key>>,
+}
+
+impl FileStore {
+    pub fn new(keys_dir: &Path, service: &str) -> Self {
+        Self {
+            keys_dir: keys_dir.to_path_buf(),
+            keyring: KeyringStore::new(service, keys_dir),
+            data_key: Mutex::new(None),
+        }
+    }
+
+    fn data_key(&self) -> Result<Key, CommandError> {
+        let mut cached = self
+            .data_key
+            .lock()
+            .map_err(|_| CommandError::internal("File store state is poisoned"))?;
+
+        if let Some(key) = *cached {
+            return Ok(key);
+        }
+
+        let key = self.load_or_create_data_key()?;
+        *cached = Some(key);
+        Ok(key)
+    }
+
+    // Load the per-install data key, generating it on first use
+    fn load_or_create_data_key(&self) -> Result<Key, CommandError> {
+        if self.keys_dir.join(DATA_KEY_MARKER).exists() {
+            let encoded = self.keyring.get_internal(DATA_KEY_ENTRY)?.ok_or_else(|| {
+                CommandError::internal("The data key is missing from the OS keyring")
+            })?;
+            return general_purpose::STANDARD
+                .decode(encoded)
+                .ok()
+                .and_then(|bytes| crypto::key_from_slice(&bytes))
+                .ok_or_else(|| CommandError::internal("Data key in the OS keyring is corrupted"));
+        }
+
+        let path = self.keys_dir.join(DATA_KEY_FILE);
+        let key = match fs::read(&path) {
+            Ok(bytes) => crypto::key_from_slice(&bytes)
+                .ok_or_else(|| CommandError::internal("Data key file is corrupted"))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let key = crypto::generate_key();
+                match create_atomic(&path, key.as_slice(), FileMode::Private) {
+                    Ok(()) => key,
+                    // Another call created the key between our read and
+                    // write; use theirs
+                    Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                        return self.load_or_create_data_key()
+                    }
+                    Err(e) => return Err(CommandError::io("Failed to create data key", e)),
+                }
+            }
+            Err(e) => return Err(CommandError::io("Failed to read data key", e)),
+        };
+
+        // If the keyring refuses the key, the file keeps working
+        if self.keyring.is_available() {
+            let _ = self.move_to_keyring(&key);
+        }
+
+        Ok(key)
+    }
+
+    // Store the data key in the keyring, then remove the key file. The file
+    // is only removed once the keyring hands back the key it was given.
+    fn move_to_keyring(&self, key: &Key) -> Result<(), CommandError> {
+        let encoded = general_purpose::STANDARD.encode(key.as_slice());
+        self.keyring.set_internal(DATA_KEY_ENTRY, &encoded)?;
+        if self.keyring.get_internal(DATA_KEY_ENTRY)?.as_deref() != Some(encoded.as_str()) {
+            return Err(CommandError::internal(
+                "The OS keyring didn't keep the data key",
+            ));
+        }
+
+        write_atomic(&self.keys_dir.join(DATA_KEY_MARKER), b"", FileMode::Private)
+            .map_err(|e| CommandError::io("Failed to write data key marker", e))?;
+        match fs::remove_file(self.keys_dir.join(DATA_KEY_FILE)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(CommandError::io("Failed to remove data key file", e)),
+        }
+    }
+}
+
+impl SecretStore for FileStore {
+    fn backend(&self) -> Backend {
+        Backend::File
+    }
+
+    fn get(&self, key: &StorageKey) -> Result<String, CommandError> {
+        let key_file = key_file_path(&self.keys_dir, key);
+
+        if !key_file.exists() {
+            return Err(CommandError::not_found(format!("Key '{}' not found", key)));
+        }
+
+        let contents =
+            fs::read(&key_file).map_err(|e| CommandError::io("Failed to read file", e))?;
+        let data_key = self.data_key()?;
+
+        if is_sealed(&contents) {
+            return open(&data_key, key, &contents);
+        }
+
+        // Migrate the legacy file so the plaintext doesn't stay on disk
+        let value = decode_legacy(&contents)?;
+        let sealed = seal(&data_key, key, &value)?;
+        write_atomic(&key_file, &sealed, FileMode::Private)
+            .map_err(|e| CommandError::io("Failed to migrate key file", e))?;
+
+        Ok(value)
+    }
+
+    fn set(&self, key: &StorageKey, value: &str) -> Result<(), CommandError> {
+        let data_key = self.data_key()?;
+        let sealed = seal(&data_key, key, value)?;
+
+        write_atomic(
+            &key_file_path(&self.keys_dir, key),
+            &sealed,
+            FileMode::Private,
+        )
+        .map_err(|e| CommandError::io("Failed to write file", e))
+    }
+
+    fn remove(&self, key: &StorageKey) -> Result<(), CommandError> {
+        let key_file = key_file_path(&self.keys_dir, key);
+
+        if !key_file.exists() {
+            return Ok(()); // Not an error if it doesn't exist
+        }
+
+        fs::remove_file(&key_file).map_err(|e| CommandError::io("Failed to delete file", e))
+    }
+
+    fn keys(&self) -> Result<Vec<StorageKey>, CommandError> {
+        let entries = fs::read_dir(&self.keys_dir)
+            .map_err(|e| CommandError::io("Failed to read keys directory", e))?;
+
+        let mut keys = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| CommandError::io("Failed to read keys directory", e))?
+                .path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("dat") {
+                continue;
+            }
+            // Files that don't carry a valid key name were never written by us
+            if let Some(key) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| StorageKey::parse(stem).ok())
+            {
+                keys.push(key);
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+}
@@ -0,0 +1,192 @@
+// Validated secure storage key names
+//
+// Key names come from the webview and end up in file names (`<key>.dat`) and
+// keyring entries, so they are checked before reaching any store: ASCII
+// letters, digits, `_`, `-` and `.` only, starting with a letter or digit, not
+// ending with a dot, at most 128 bytes, and never a Windows device name.
+use serde::Serialize;
+use std::fmt;
+
+const MAX_KEY_LEN: usize = 128;
+
+// Device names Windows reserves regardless of extension (`NUL.dat` included)
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StorageKey(String);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "camelCase")]
+pub enum InvalidKey {
+    Empty,
+    TooLong { max: usize },
+    InvalidCharacter { character: char },
+    InvalidStart,
+    TrailingDot,
+    Reserved,
+}
+
+impl StorageKey {
+    pub fn parse(key: &str) -> Result<Self, InvalidKey> {
+        if key.is_empty() {
+            return Err(InvalidKey::Empty);
+        }
+
+        if key.len() > MAX_KEY_LEN {
+            return Err(InvalidKey::TooLong { max: MAX_KEY_LEN });
+        }
+
+        if let Some(character) = key
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')))
+        {
+            return Err(InvalidKey::InvalidCharacter { character });
+        }
+
+        if !key.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            return Err(InvalidKey::InvalidStart);
+        }
+
+        if key.ends_with('.') {
+            return Err(InvalidKey::TrailingDot);
+        }
+
+        let stem = key.split('.').next().unwrap_or(key);
+        if WINDOWS_RESERVED_NAMES
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+        {
+            return Err(InvalidKey::Reserved);
+        }
+
+        Ok(Self(key.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for StorageKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidKey::Empty => write!(f, "key is empty"),
+            InvalidKey::TooLong { max } => write!(f, "key is longer than {} bytes", max),
+            InvalidKey::InvalidCharacter { character } => {
+                write!(f, "key contains invalid character {:?}", character)
+            }
+            InvalidKey::InvalidStart => write!(f, "key must start with a letter or digit"),
+            InvalidKey::TrailingDot => write!(f, "key must not end with a dot"),
+            InvalidKey::Reserved => write!(f, "key is a reserved name"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(key: &str) -> InvalidKey {
+        StorageKey::parse(key).expect_err(key)
+    }
+
+    #[test]
+    fn accepts_plain_names() {
+        for key in [
+            "localekit_openai-api-key",
+            "a",
+            "key.v2",
+            "0-first",
+            "con1",
+            "nullable",
+            &"k".repeat(MAX_KEY_LEN),
+        ] {
+            assert_eq!(StorageKey::parse(key).unwrap().as_str(), key);
+        }
+    }
+
+    #[test]
+    fn rejects_traversal() {
+        assert_eq!(
+            rejected("../../.bashrc"),
+            InvalidKey::InvalidCharacter { character: '/' }
+        );
+        assert_eq!(
+            rejected("keys/../../.bashrc"),
+            InvalidKey::InvalidCharacter { character: '/' }
+        );
+        assert_eq!(rejected(".."), InvalidKey::InvalidStart);
+        assert_eq!(
+            rejected("a\\..\\b"),
+            InvalidKey::InvalidCharacter { character: '\\' }
+        );
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        assert_eq!(
+            rejected("/etc/passwd"),
+            InvalidKey::InvalidCharacter { character: '/' }
+        );
+        assert_eq!(
+            rejected("C:\\x"),
+            InvalidKey::InvalidCharacter { character: ':' }
+        );
+        assert_eq!(
+            rejected("\\\\server\\share"),
+            InvalidKey::InvalidCharacter { character: '\\' }
+        );
+    }
+
+    #[test]
+    fn rejects_nul_and_control_characters() {
+        assert_eq!(
+            rejected("key\0.dat"),
+            InvalidKey::InvalidCharacter { character: '\0' }
+        );
+        assert_eq!(
+            rejected("key\n"),
+            InvalidKey::InvalidCharacter { character: '\n' }
+        );
+        assert_eq!(
+            rejected("clé"),
+            InvalidKey::InvalidCharacter { character: 'é' }
+        );
+    }
+
+    #[test]
+    fn rejects_windows_reserved_names() {
+        for key in [
+            "con",
+            "CON",
+            "NUL.txt",
+            "nul.dat",
+            "com1",
+            "LPT9.tar.gz",
+            "aux",
+        ] {
+            assert_eq!(rejected(key), InvalidKey::Reserved, "{}", key);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_long_and_dotted_names() {
+        assert_eq!(rejected(""), InvalidKey::Empty);
+        assert_eq!(
+            rejected(&"k".repeat(MAX_KEY_LEN + 1)),
+            InvalidKey::TooLong { max: MAX_KEY_LEN }
+        );
+        assert_eq!(rejected(".hidden"), InvalidKey::InvalidStart);
+        assert_eq!(rejected("-flag"), InvalidKey::InvalidStart);
+        assert_eq!(rejected("key."), InvalidKey::TrailingDot);
+    }
+}
@@ -0,0 +1,79 @@
+// Per-key timestamps for stored secrets
+//
+// Neither the keyring nor the file store keeps reliable created/updated times
+// (many filesystems don't report creation time), so the secure storage
+// commands record them in `.keys/metadata.json`, keyed by key name. Keys that
+// predate this file simply have no timestamps.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use super::StorageKey;
+use crate::error::CommandError;
+use crate::fs_util::{now_millis, write_atomic, FileMode};
+
+const METADATA_FILE: &str = "metadata.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyTimestamps {
+    // Milliseconds since the Unix epoch
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+pub fn load(keys_dir: &Path) -> Result<BTreeMap<String, KeyTimestamps>, CommandError> {
+    match fs::read_to_string(keys_dir.join(METADATA_FILE)) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| CommandError::json("Failed to parse key metadata", e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(CommandError::io("Failed to read key metadata", e)),
+    }
+}
+
+fn save(keys_dir: &Path, metadata: &BTreeMap<String, KeyTimestamps>) -> Result<(), CommandError> {
+    let content = serde_json::to_string_pretty(metadata)
+        .map_err(|e| CommandError::json("Failed to serialize key metadata", e))?;
+
+    write_atomic(
+        &keys_dir.join(METADATA_FILE),
+        content.as_bytes(),
+        FileMode::Private,
+    )
+    .map_err(|e| CommandError::io("Failed to write key metadata", e))
+}
+
+pub fn record_write(keys_dir: &Path, key: &StorageKey) -> Result<(), CommandError> {
+    let mut metadata = load(keys_dir)?;
+    let now = now_millis();
+
+    metadata
+        .entry(key.to_string())
+        .and_modify(|timestamps| timestamps.updated_at = now)
+        .or_insert(KeyTimestamps {
+            created_at: now,
+            updated_at: now,
+        });
+
+    save(keys_dir, &metadata)
+}
+
+pub fn record_remove(keys_dir: &Path, key: &StorageKey) -> Result<(), CommandError> {
+    let mut metadata = load(keys_dir)?;
+
+    if metadata.remove(key.as_str()).is_some() {
+        save(keys_dir, &metadata)?;
+    }
+
+    Ok(())
+}
+
+pub fn clear(keys_dir: &Path) -> Result<(), CommandError> {
+    match fs::remove_file(keys_dir.join(METADATA_FILE)) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(CommandError::io("Failed to remove key metadata", e)),
+    }
+}
@@ -0,0 +1,316 @@
+// Pluggable secret storage
+//
+// API keys go through a `SecretStore`: either the OS keyring or the encrypted
+// `.keys` file store. The backend is chosen at runtime. An explicit preference
+// (LOCALEKIT_SECRET_BACKEND, then `.keys/backend.json`) wins; on "auto" the
+// keyring is used when a keyring service answers and the file store otherwise,
+// which keeps headless Linux and CI working without a keyring daemon. The
+// first time "auto" finds a keyring, the keys already in the file store are
+// moved into it (see `resolve_store`).
+pub mod bundle;
+mod crypto;
+mod file;
+mod key;
+pub mod metadata;
+mod os_keyring;
+mod vault;
+
+use file::FileStore;
+use os_keyring::KeyringStore;
+
+pub use key::StorageKey;
+pub use vault::{Vault, VaultStatus};
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::error::{CommandError, ErrorCode};
+use crate::fs_util::{write_atomic, FileMode};
+
+const PREFERENCE_FILE: &str = "backend.json";
+const BACKEND_ENV_VAR: &str = "LOCALEKIT_SECRET_BACKEND";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    File,
+    Keyring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendPreference {
+    #[default]
+    Auto,
+    File,
+    Keyring,
+}
+
+pub trait SecretStore: Send + Sync {
+    fn backend(&self) -> Backend;
+    fn get(&self, key: &StorageKey) -> Result<String, CommandError>;
+    fn set(&self, key: &StorageKey, value: &str) -> Result<(), CommandError>;
+    fn remove(&self, key: &StorageKey) -> Result<(), CommandError>;
+    fn keys(&self) -> Result<Vec<StorageKey>, CommandError>;
+}
+
+// Validate a key name coming from the frontend. Rejections carry the key and
+// the reason (see `InvalidKey`) as details.
+pub fn parse_key(key: &str) -> Result<StorageKey, CommandError> {
+    StorageKey::parse(key).map_err(|reason| {
+        CommandError::new(
+            ErrorCode::InvalidKey,
+            format!("Invalid key '{}': {}", key.escape_default(), reason),
+        )
+        .with_details(serde_json::json!({ "key": key, "reason": reason }))
+    })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendStatus {
+    preference: BackendPreference,
+    active: Backend,
+    keyring_available: bool,
+}
+
+// What `secure_storage_list` reports for each key. The preview is only
+// available when the value can be read (i.e. the vault isn't locked).
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredKeyInfo {
+    key: String,
+    backend: Backend,
+    created_at: Option<u64>,
+    updated_at: Option<u64>,
+    preview: Option<String>,
+}
+
+// Managed state holding the resolved store so the keyring isn't probed on
+// every command
+#[derive(Default)]
+pub struct SecureStorage {
+    active: Mutex<Option<Arc<dyn SecretStore>>>,
+}
+
+impl SecureStorage {
+    pub fn store(
+        &self,
+        keys_dir: &Path,
+        service: &str,
+    ) -> Result<Arc<dyn SecretStore>, CommandError> {
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| CommandError::internal("Secure storage state is poisoned"))?;
+
+        if let Some(store) = active.as_ref() {
+            return Ok(store.clone());
+        }
+
+        let store = resolve_store(keys_dir, service, load_preference(keys_dir)?, || {
+            KeyringStore::new(service, keys_dir).is_available()
+        })?;
+        *active = Some(store.clone());
+        Ok(store)
+    }
+
+    pub fn status(&self, keys_dir: &Path, service: &str) -> Result<BackendStatus, CommandError> {
+        let store = self.store(keys_dir, service)?;
+
+        Ok(BackendStatus {
+            preference: load_preference(keys_dir)?,
+            active: store.backend(),
+            keyring_available: KeyringStore::new(service, keys_dir).is_available(),
+        })
+    }
+
+    // Persist a new preference and move existing secrets over if the active
+    // backend changes as a result
+    pub fn set_preference(
+        &self,
+        keys_dir: &Path,
+        service: &str,
+        preference: BackendPreference,
+    ) -> Result<BackendStatus, CommandError> {
+        if preference == BackendPreference::Keyring
+            && !KeyringStore::new(service, keys_dir).is_available()
+        {
+            return Err(CommandError::new(
+                ErrorCode::Unavailable,
+                "OS keyring is not available",
+            ));
+        }
+
+        let current = self.store(keys_dir, service)?;
+        save_preference(keys_dir, preference)?;
+        let next = resolve_store(keys_dir, service, load_preference(keys_dir)?, || {
+            KeyringStore::new(service, keys_dir).is_available()
+        })?;
+
+        if next.backend() != current.backend() {
+            migrate(current.as_ref(), next.as_ref())?;
+        }
+
+        *self
+            .active
+            .lock()
+            .map_err(|_| CommandError::internal("Secure storage state is poisoned"))? = Some(next);
+
+        self.status(keys_dir, service)
+    }
+}
+
+// `keyring_available` is only asked on "auto", since probing the keyring can
+// mean a D-Bus round trip
+fn open_store(
+    keys_dir: &Path,
+    service: &str,
+    preference: BackendPreference,
+    keyring_available: impl FnOnce() -> bool,
+) -> Arc<dyn SecretStore> {
+    let use_keyring = match preference {
+        BackendPreference::File => false,
+        BackendPreference::Keyring => true,
+        BackendPreference::Auto => keyring_available(),
+    };
+
+    if use_keyring {
+        Arc::new(KeyringStore::new(service, keys_dir))
+    } else {
+        Arc::new(FileStore::new(keys_dir, service))
+    }
+}
+
+// Open the store for `preference`. On "auto" the keyring wins as soon as one
+// answers: keys still in the file store are moved into it and the choice is
+// saved, so a keyring outage later on fails loudly instead of showing an
+// empty file store. If moving fails, the file store stays active and the move
+// is tried again next time.
+fn resolve_store(
+    keys_dir: &Path,
+    service: &str,
+    preference: BackendPreference,
+    keyring_available: impl FnOnce() -> bool,
+) -> Result<Arc<dyn SecretStore>, CommandError> {
+    let store = open_store(keys_dir, service, preference, keyring_available);
+    if preference != BackendPreference::Auto || store.backend() != Backend::Keyring {
+        return Ok(store);
+    }
+
+    let files = FileStore::new(keys_dir, service);
+    if migrate(&files, store.as_ref()).is_err() {
+        return Ok(Arc::new(files));
+    }
+    save_preference(keys_dir, BackendPreference::Keyring)?;
+
+    Ok(store)
+}
+
+fn load_preference(keys_dir: &Path) -> Result<BackendPreference, CommandError> {
+    if let Ok(value) = std::env::var(BACKEND_ENV_VAR) {
+        return serde_json::from_value(serde_json::Value::String(value.to_lowercase())).map_err(
+            |_| {
+                CommandError::invalid_input(format!("Invalid {} value: {}", BACKEND_ENV_VAR, value))
+            },
+        );
+    }
+
+    match fs::read_to_string(keys_dir.join(PREFERENCE_FILE)) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| CommandError::json("Failed to parse backend preference", e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(BackendPreference::default()),
+        Err(e) => Err(CommandError::io("Failed to read backend preference", e)),
+    }
+}
+
+fn save_preference(keys_dir: &Path, preference: BackendPreference) -> Result<(), CommandError> {
+    let content = serde_json::to_string(&preference)
+        .map_err(|e| CommandError::json("Failed to serialize backend preference", e))?;
+
+    write_atomic(
+        &keys_dir.join(PREFERENCE_FILE),
+        content.as_bytes(),
+        FileMode::Private,
+    )
+    .map_err(|e| CommandError::io("Failed to write backend preference", e))
+}
+
+pub fn list_keys(
+    keys_dir: &Path,
+    store: &dyn SecretStore,
+    vault: &Vault,
+) -> Result<Vec<StoredKeyInfo>, CommandError> {
+    let timestamps = metadata::load(keys_dir)?;
+
+    Ok(store
+        .keys()?
+        .into_iter()
+        .map(|key| {
+            let recorded = timestamps.get(key.as_str());
+            let preview = store
+                .get(&key)
+                .ok()
+                .and_then(|stored| vault.open(keys_dir, &key, &stored).ok())
+                .map(|value| mask_value(&value));
+
+            StoredKeyInfo {
+                key: key.to_string(),
+                backend: store.backend(),
+                created_at: recorded.map(|t| t.created_at),
+                updated_at: recorded.map(|t| t.updated_at),
+                preview,
+            }
+        })
+        .collect())
+}
+
+// Remove every key from the store along with its metadata. Returns how many
+// keys were removed.
+pub fn clear_keys(keys_dir: &Path, store: &dyn SecretStore) -> Result<usize, CommandError> {
+    let keys = store.keys()?;
+
+    for key in &keys {
+        store.remove(key)?;
+    }
+    metadata::clear(keys_dir)?;
+
+    Ok(keys.len())
+}
+
+// Show just enough of a secret to recognise it, e.g. `sk-…a9F2`
+fn mask_value(value: &str) -> String {
+    const PREFIX_LEN: usize = 3;
+    const SUFFIX_LEN: usize = 4;
+    // Short values would be mostly revealed by the preview
+    const MIN_PREVIEW_LEN: usize = 12;
+
+    let chars: Vec<char> = value.trim().chars().collect();
+    if chars.len() < MIN_PREVIEW_LEN {
+        return "…".to_string();
+    }
+
+    let prefix: String = chars[..PREFIX_LEN].iter().collect();
+    let suffix: String = chars[chars.len() - SUFFIX_LEN..].iter().collect();
+    format!("{}…{}", prefix, suffix)
+}
+
+// Copy every secret first and only then remove the originals, so a failure
+// part-way through never loses a key
+fn migrate(from: &dyn SecretStore, to: &dyn SecretStore) -> Result<(), CommandError> {
+    let keys = from.keys()?;
+
+    for key in &keys {
+        let value = from.get(key)?;
+        to.set(key, &value)?;
+    }
+
+    for key in &keys {
+        from.remove(key)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,247 @@
+// OS keyring secret store
+//
+// Uses the platform credential store through the `keyring` crate: Keychain on
+// macOS, Credential Manager on Windows and the Secret Service D-Bus API on
+// Linux. The Secret Service client connects to whatever bus
+// DBUS_SESSION_BUS_ADDRESS points at, so a local stand-in daemon works too.
+//
+// Keyrings can't be enumerated portably, so the names of stored keys are kept
+// in `.keys/keyring-index.json` (names only, never values).
+use keyring::Entry;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use super::{Backend, SecretStore, StorageKey};
+use crate::error::{CommandError, ErrorCode};
+use crate::fs_util::{write_atomic, FileMode};
+
+const INDEX_FILE: &str = "keyring-index.json";
+const PROBE_KEY: &str = "__localekit_probe__";
+
+pub struct KeyringStore {
+    service: String,
+    index_path: PathBuf,
+}
+
+impl KeyringStore {
+    pub fn new(service: &str, keys_dir: &Path) -> Self {
+        Self {
+            service: service.to_string(),
+            index_path: keys_dir.join(INDEX_FILE),
+        }
+    }
+
+    // A keyring is usable when a lookup either succeeds or reports a missing
+    // entry; anything else means there is no daemon or we can't reach it
+    pub fn is_available(&self) -> bool {
+        match Entry::new(&self.service, PROBE_KEY).and_then(|entry| entry.get_password()) {
+            Ok(_) | Err(keyring::Error::NoEntry) => true,
+            Err(_) => false,
+        }
+    }
+
+    // Entries the app keeps for itself, such as the file store's data key.
+    // They are left out of the index, and their names aren't valid
+    // StorageKeys, so they can't clash with a stored secret.
+    pub fn get_internal(&self, name: &str) -> Result<Option<String>, CommandError> {
+        let entry = Entry::new(&self.service, name)
+            .map_err(|e| keyring_error("Failed to open keyring entry", e))?;
+
+        match entry.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error("Failed to read from keyring", e)),
+        }
+    }
+
+    pub fn set_internal(&self, name: &str, value: &str) -> Result<(), CommandError> {
+        Entry::new(&self.service, name)
+            .and_then(|entry| entry.set_password(value))
+            .map_err(|e| keyring_error("Failed to write to keyring", e))
+    }
+
+    fn entry(&self, key: &StorageKey) -> Result<Entry, CommandError> {
+        Entry::new(&self.service, key.as_str())
+            .map_err(|e| keyring_error("Failed to open keyring entry", e))
+    }
+
+    fn read_index(&self) -> Result<Vec<StorageKey>, CommandError> {
+        let names: Vec<String> = match fs::read_to_string(&self.index_path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| CommandError::json("Failed to parse keyring index", e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(CommandError::io("Failed to read keyring index", e)),
+        };
+
+        Ok(names
+            .iter()
+            .filter_map(|name| StorageKey::parse(name).ok())
+            .collect())
+    }
+
+    fn write_index(&self, keys: &[StorageKey]) -> Result<(), CommandError> {
+        let names: Vec<&str> = keys.iter().map(StorageKey::as_str).collect();
+        let content = serde_json::to_string(&names)
+            .map_err(|e| CommandError::json("Failed to serialize keyring index", e))?;
+
+        write_atomic(&self.index_path, content.as_bytes(), FileMode::Private)
+            .map_err(|e| CommandError::io("Failed to write keyring index", e))
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn backend(&self) -> Backend {
+        Backend::Keyring
+    }
+
+    fn get(&self, key: &StorageKey) -> Result<String, CommandError> {
+        match self.entry(key)?.get_password() {
+            Ok(value) => Ok(value),
+            Err(keyring::Error::NoEntry) => {
+                Err(CommandError::not_found(format!("Key '{}' not found", key)))
+            }
+            Err(e) => Err(keyring_error("Failed to read from keyring", e)),
+        }
+    }
+
+    fn set(&self, key: &StorageKey, value: &str) -> Result<(), CommandError> {
+        self.entry(key)?
+            .set_password(value)
+            .map_err(|e| keyring_error("Failed to write to keyring", e))?;
+
+        let mut keys = self.read_index()?;
+        if !keys.contains(key) {
+            keys.push(key.clone());
+            keys.sort();
+            self.write_index(&keys)?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, key: &StorageKey) -> Result<(), CommandError> {
+        match self.entry(key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => return Err(keyring_error("Failed to delete from keyring", e)),
+        }
+
+        let mut keys = self.read_index()?;
+        let before = keys.len();
+        keys.retain(|existing| existing != key);
+        if keys.len() != before {
+            self.write_index(&keys)?;
+        }
+
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<StorageKey>, CommandError> {
+        self.read_index()
+    }
+}
+
+// Keyring failures are almost always the service being unreachable or
+// refusing access, which the frontend reports differently from I/O errors
+fn keyring_error(context: &str, error: keyring::Error) -> CommandError {
+    CommandError::new(ErrorCode::Unavailable, format!("{}: {}", context, error))
+}
+
+// The keyring tests talk to whatever Secret Service DBUS_SESSION_BUS_ADDRESS
+// points at, so they are ignored by default. Run them against a throwaway
+// daemon, as the Rust Tests workflow does (.github/workflows/rust-tests.yml):
+//
+//   dbus-run-session -- sh -c 'echo -n test |
+//     gnome-keyring-daemon --unlock --components=secrets && cargo test -- --ignored'
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use crate::secure_storage::file::FileStore;
+    use crate::secure_storage::{migrate, resolve_store, BackendPreference, PREFERENCE_FILE};
+
+    fn key(name: &str) -> StorageKey {
+        StorageKey::parse(name).unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a Secret Service daemon"]
+    fn keyring_store_round_trip() {
+        let dir = test_dir("keyring-round-trip");
+        let store = KeyringStore::new("localekit-test-round-trip", &dir);
+        assert!(store.is_available());
+
+        store.set(&key("alpha"), "one").unwrap();
+        store.set(&key("beta"), "two").unwrap();
+        store.set(&key("alpha"), "uno").unwrap();
+        assert_eq!(store.get(&key("alpha")).unwrap(), "uno");
+        assert_eq!(store.keys().unwrap(), vec![key("alpha"), key("beta")]);
+
+        store.remove(&key("alpha")).unwrap();
+        let missing = store.get(&key("alpha")).unwrap_err();
+        assert_eq!(missing.code, ErrorCode::NotFound);
+        assert_eq!(store.keys().unwrap(), vec![key("beta")]);
+
+        store.remove(&key("beta")).unwrap();
+        // Removing a missing key is not an error
+        store.remove(&key("beta")).unwrap();
+        assert!(store.keys().unwrap().is_empty());
+    }
+
+    #[test]
+    #[ignore = "needs a Secret Service daemon"]
+    fn migrate_moves_keys_between_file_store_and_keyring() {
+        let dir = test_dir("keyring-migrate");
+        let service = "localekit-test-migrate";
+        let files = FileStore::new(&dir, service);
+        let keyring = KeyringStore::new(service, &dir);
+        files.set(&key("openai-api-key"), "sk-one").unwrap();
+        files.set(&key("mistral-api-key"), "sk-two").unwrap();
+
+        migrate(&files, &keyring).unwrap();
+        assert!(files.keys().unwrap().is_empty());
+        assert_eq!(keyring.get(&key("openai-api-key")).unwrap(), "sk-one");
+        assert_eq!(keyring.get(&key("mistral-api-key")).unwrap(), "sk-two");
+
+        migrate(&keyring, &files).unwrap();
+        assert!(keyring.keys().unwrap().is_empty());
+        assert_eq!(files.get(&key("openai-api-key")).unwrap(), "sk-one");
+        assert_eq!(files.get(&key("mistral-api-key")).unwrap(), "sk-two");
+    }
+
+    #[test]
+    #[ignore = "needs a Secret Service daemon"]
+    fn auto_moves_file_store_into_keyring() {
+        let dir = test_dir("keyring-auto");
+        let service = "localekit-test-auto";
+        let files = FileStore::new(&dir, service);
+        files.set(&key("openai-api-key"), "sk-one").unwrap();
+
+        let store = resolve_store(&dir, service, BackendPreference::Auto, || {
+            KeyringStore::new(service, &dir).is_available()
+        })
+        .unwrap();
+        assert_eq!(store.backend(), Backend::Keyring);
+        assert_eq!(store.get(&key("openai-api-key")).unwrap(), "sk-one");
+        assert!(files.keys().unwrap().is_empty());
+
+        let saved = fs::read_to_string(dir.join(PREFERENCE_FILE)).unwrap();
+        assert_eq!(saved, "\"keyring\"");
+        store.remove(&key("openai-api-key")).unwrap();
+    }
+
+    #[test]
+    fn auto_falls_back_to_file_store_without_a_keyring() {
+        let dir = test_dir("keyring-fallback");
+        let store = resolve_store(
+            &dir,
+            "localekit-test-fallback",
+            BackendPreference::Auto,
+            || false,
+        )
+        .unwrap();
+
+        assert_eq!(store.backend(), Backend::File);
+        assert!(!dir.join(PREFERENCE_FILE).exists());
+    }
+}
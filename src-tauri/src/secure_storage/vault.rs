@@ -0,0 +1,561 @@
+// Optional master-passphrase vault
+//
+// When the vault is enabled, values handed to the active SecretStore are
+// sealed under a key derived from the user's passphrase with Argon2id, so the
+// store (keyring or files) only ever sees `lkvault1:<base64>` blobs. The
+// derived key lives in memory while the vault is unlocked and is dropped on
+// `lock()` or once the vault has been idle for longer than the auto-lock
+// timeout.
+//
+// `.keys/vault.json` holds the salt, the KDF parameters, the timeout and a
+// sealed check value used to tell a wrong passphrase from corrupted data. It
+// is read once and kept in memory afterwards. While the vault is enabled,
+// every stored value must be sealed; anything else is refused rather than
+// handed out, since only another writer could have put it there.
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use super::crypto::{self, KdfParams, Key, SALT_LEN};
+use super::{SecretStore, StorageKey};
+use crate::error::{CommandError, ErrorCode};
+use crate::fs_util::{write_atomic, FileMode};
+
+const VAULT_FILE: &str = "vault.json";
+const SEALED_PREFIX: &str = "lkvault1:";
+const CHECK_AAD: &[u8] = b"localekit-vault-check";
+const CHECK_PLAINTEXT: &[u8] = b"localekit-vault";
+const MIN_PASSPHRASE_LEN: usize = 8;
+const DEFAULT_AUTO_LOCK_SECONDS: u64 = 300;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VaultConfig {
+    salt: String,
+    #[serde(flatten)]
+    kdf: KdfParams,
+    check: String,
+    // 0 disables auto-lock
+    auto_lock_seconds: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    enabled: bool,
+    unlocked: bool,
+    auto_lock_seconds: Option<u64>,
+}
+
+struct Session {
+    key: Key,
+    last_used: Instant,
+}
+
+// Managed state holding the vault file and the unlocked vault key, if any
+#[derive(Default)]
+pub struct Vault {
+    // The vault file once read, None inside when the vault is disabled
+    config: Mutex<Option<Option<VaultConfig>>>,
+    session: Mutex<Option<Session>>,
+}
+
+impl Vault {
+    pub fn status(&self, keys_dir: &Path) -> Result<VaultStatus, CommandError> {
+        let config = self.config(keys_dir)?;
+
+        Ok(VaultStatus {
+            enabled: config.is_some(),
+            unlocked: config.is_some() && self.session()?.is_some(),
+            auto_lock_seconds: config.map(|config| config.auto_lock_seconds),
+        })
+    }
+
+    // Turn the vault on and re-seal every secret already in the store
+    pub fn enable(
+        &self,
+        keys_dir: &Path,
+        store: &dyn SecretStore,
+        passphrase: &str,
+        auto_lock_seconds: Option<u64>,
+    ) -> Result<VaultStatus, CommandError> {
+        if self.config(keys_dir)?.is_some() {
+            return Err(CommandError::invalid_input("Vault is already enabled"));
+        }
+
+        if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+            return Err(CommandError::new(
+                ErrorCode::WeakPassphrase,
+                format!(
+                    "Passphrase must be at least {} characters",
+                    MIN_PASSPHRASE_LEN
+                ),
+            ));
+        }
+
+        // Read everything before touching the store so a bad entry aborts
+        // before anything is rewritten
+        let keys = store.keys()?;
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            let value = store.get(&key)?;
+            values.push((key, value));
+        }
+
+        let salt = crypto::random_bytes::<SALT_LEN>()?;
+        let config = VaultConfig {
+            salt: general_purpose::STANDARD.encode(salt),
+            kdf: KdfParams::default(),
+            check: String::new(),
+            auto_lock_seconds: auto_lock_seconds.unwrap_or(DEFAULT_AUTO_LOCK_SECONDS),
+        };
+        let vault_key = derive_key(&config, passphrase)?;
+        let config = VaultConfig {
+            check: general_purpose::STANDARD.encode(crypto::encrypt(
+                &vault_key,
+                CHECK_AAD,
+                CHECK_PLAINTEXT,
+            )?),
+            ..config
+        };
+
+        let mut changes = Vec::with_capacity(values.len());
+        for (key, value) in values {
+            let sealed = seal_with(&vault_key, &key, &value)?;
+            changes.push((key, value, sealed));
+        }
+        rewrite_all(store, &changes, || self.set_config(keys_dir, Some(config)))?;
+
+        *self.session()? = Some(Session {
+            key: vault_key,
+            last_used: Instant::now(),
+        });
+
+        self.status(keys_dir)
+    }
+
+    // Turn the vault off, writing every secret back to the store unsealed
+    pub fn disable(
+        &self,
+        keys_dir: &Path,
+        store: &dyn SecretStore,
+        passphrase: &str,
+    ) -> Result<VaultStatus, CommandError> {
+        let config = self.require_config(keys_dir)?;
+        let vault_key = verify_passphrase(&config, passphrase)?;
+
+        let keys = store.keys()?;
+        let mut changes = Vec::with_capacity(keys.len());
+        for key in keys {
+            let stored = store.get(&key)?;
+            let value = open_with(&vault_key, &key, &stored)?;
+            changes.push((key, stored, value));
+        }
+
+        rewrite_all(store, &changes, || self.set_config(keys_dir, None))?;
+        *self.session()? = None;
+
+        self.status(keys_dir)
+    }
+
+    pub fn unlock(&self, keys_dir: &Path, passphrase: &str) -> Result<VaultStatus, CommandError> {
+        let config = self.require_config(keys_dir)?;
+        let vault_key = verify_passphrase(&config, passphrase)?;
+
+        *self.session()? = Some(Session {
+            key: vault_key,
+            last_used: Instant::now(),
+        });
+
+        self.status(keys_dir)
+    }
+
+    // Returns whether the vault was unlocked before the call
+    pub fn lock(&self) -> Result<bool, CommandError> {
+        Ok(self.session()?.take().is_some())
+    }
+
+    pub fn set_auto_lock(
+        &self,
+        keys_dir: &Path,
+        auto_lock_seconds: u64,
+    ) -> Result<VaultStatus, CommandError> {
+        let config = self.require_config(keys_dir)?;
+
+        // Changing the timeout needs an unlocked vault, otherwise anyone could
+        // disable auto-lock for the next session
+        if self.session()?.is_none() {
+            return Err(vault_locked());
+        }
+
+        self.set_config(
+            keys_dir,
+            Some(VaultConfig {
+                auto_lock_seconds,
+                ..config
+            }),
+        )?;
+
+        self.status(keys_dir)
+    }
+
+    // Lock the vault if it has been idle past its timeout. Returns true when
+    // this call locked it, so the caller can notify the frontend. Only looks
+    // at the state in memory: an unlocked vault has its file loaded.
+    pub fn lock_if_idle(&self) -> bool {
+        let timeout = match self.config.lock().as_deref() {
+            Ok(Some(Some(config))) if config.auto_lock_seconds > 0 => {
+                Duration::from_secs(config.auto_lock_seconds)
+            }
+            _ => return false,
+        };
+
+        let Ok(mut session) = self.session.lock() else {
+            return false;
+        };
+
+        match session.as_ref() {
+            Some(active) if active.last_used.elapsed() >= timeout => {
+                *session = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Prepare a value for the store: sealed when the vault is enabled,
+    // unchanged otherwise
+    pub fn seal(
+        &self,
+        keys_dir: &Path,
+        key: &StorageKey,
+        value: &str,
+    ) -> Result<String, CommandError> {
+        if self.config(keys_dir)?.is_none() {
+            return Ok(value.to_string());
+        }
+
+        let vault_key = self.touch()?;
+        seal_with(&vault_key, key, value)
+    }
+
+    // Recover a value read from the store
+    pub fn open(
+        &self,
+        keys_dir: &Path,
+        key: &StorageKey,
+        stored: &str,
+    ) -> Result<String, CommandError> {
+        if self.config(keys_dir)?.is_none() {
+            return Ok(stored.to_string());
+        }
+
+        let vault_key = self.touch()?;
+        open_with(&vault_key, key, stored)
+    }
+
+    // Hand out the vault key and reset the idle timer
+    fn touch(&self) -> Result<Key, CommandError> {
+        let mut session = self.session()?;
+
+        match session.as_mut() {
+            Some(active) => {
+                active.last_used = Instant::now();
+                Ok(active.key)
+            }
+            None => Err(vault_locked()),
+        }
+    }
+
+    fn config(&self, keys_dir: &Path) -> Result<Option<VaultConfig>, CommandError> {
+        let mut cached = self.lock_config()?;
+        if cached.is_none() {
+            *cached = Some(load_config(keys_dir)?);
+        }
+
+        Ok(cached.clone().flatten())
+    }
+
+    fn require_config(&self, keys_dir: &Path) -> Result<VaultConfig, CommandError> {
+        self.config(keys_dir)?
+            .ok_or_else(|| CommandError::invalid_input("Vault is not enabled"))
+    }
+
+    // Write (or, for None, remove) the vault file and update the copy in
+    // memory
+    fn set_config(&self, keys_dir: &Path, config: Option<VaultConfig>) -> Result<(), CommandError> {
+        let mut cached = self.lock_config()?;
+        match &config {
+            Some(config) => save_config(keys_dir, config)?,
+            None => fs::remove_file(keys_dir.join(VAULT_FILE))
+                .map_err(|e| CommandError::io("Failed to remove vault file", e))?,
+        }
+
+        *cached = Some(config);
+        Ok(())
+    }
+
+    fn lock_config(&self) -> Result<MutexGuard<'_, Option<Option<VaultConfig>>>, CommandError> {
+        self.config
+            .lock()
+            .map_err(|_| CommandError::internal("Vault state is poisoned"))
+    }
+
+    fn session(&self) -> Result<MutexGuard<'_, Option<Session>>, CommandError> {
+        self.session
+            .lock()
+            .map_err(|_| CommandError::internal("Vault state is poisoned"))
+    }
+}
+
+fn vault_locked() -> CommandError {
+    CommandError::new(ErrorCode::VaultLocked, "Vault is locked")
+}
+
+fn load_config(keys_dir: &Path) -> Result<Option<VaultConfig>, CommandError> {
+    match fs::read_to_string(keys_dir.join(VAULT_FILE)) {
+        Ok(content) => serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| CommandError::json("Failed to parse vault file", e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(CommandError::io("Failed to read vault file", e)),
+    }
+}
+
+fn save_config(keys_dir: &Path, config: &VaultConfig) -> Result<(), CommandError> {
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| CommandError::json("Failed to serialize vault file", e))?;
+
+    write_atomic(
+        &keys_dir.join(VAULT_FILE),
+        content.as_bytes(),
+        FileMode::Private,
+    )
+    .map_err(|e| CommandError::io("Failed to write vault file", e))
+}
+
+fn derive_key(config: &VaultConfig, passphrase: &str) -> Result<Key, CommandError> {
+    let salt = general_purpose::STANDARD
+        .decode(&config.salt)
+        .map_err(|e| CommandError::internal(format!("Failed to decode vault salt: {}", e)))?;
+
+    crypto::derive_key(passphrase, &salt, config.kdf)
+}
+
+fn verify_passphrase(config: &VaultConfig, passphrase: &str) -> Result<Key, CommandError> {
+    let vault_key = derive_key(config, passphrase)?;
+    let check = general_purpose::STANDARD
+        .decode(&config.check)
+        .map_err(|e| CommandError::internal(format!("Failed to decode vault check: {}", e)))?;
+
+    match crypto::decrypt(&vault_key, CHECK_AAD, &check) {
+        Some(plaintext) if plaintext == CHECK_PLAINTEXT => Ok(vault_key),
+        _ => Err(CommandError::new(
+            ErrorCode::WrongPassphrase,
+            "Wrong passphrase",
+        )),
+    }
+}
+
+// Write the new value of every `(key, original, updated)` entry, then run
+// `commit`. If a write or the commit fails, every entry touched so far gets
+// its original value back: a value sealed under a vault that was never saved
+// (or left plain under one that still is) could not be read again.
+fn rewrite_all(
+    store: &dyn SecretStore,
+    changes: &[(StorageKey, String, String)],
+    commit: impl FnOnce() -> Result<(), CommandError>,
+) -> Result<(), CommandError> {
+    let mut touched = 0;
+    let mut result = Ok(());
+    for (key, _, updated) in changes {
+        touched += 1;
+        if let Err(e) = store.set(key, updated) {
+            result = Err(e);
+            break;
+        }
+    }
+    let result = result.and_then(|()| commit());
+
+    if result.is_err() {
+        for (key, original, _) in &changes[..touched] {
+            let _ = store.set(key, original);
+        }
+    }
+    result
+}
+
+fn seal_with(vault_key: &Key, key: &StorageKey, value: &str) -> Result<String, CommandError> {
+    let sealed = crypto::encrypt(vault_key, key.as_str().as_bytes(), value.as_bytes())?;
+    Ok(format!(
+        "{}{}",
+        SEALED_PREFIX,
+        general_purpose::STANDARD.encode(sealed)
+    ))
+}
+
+fn open_with(vault_key: &Key, key: &StorageKey, stored: &str) -> Result<String, CommandError> {
+    // Enabling the vault seals every existing value, so a plain one was
+    // written behind the vault's back
+    let Some(encoded) = stored.strip_prefix(SEALED_PREFIX) else {
+        return Err(CommandError::internal(format!(
+            "Key '{}' is not sealed by the vault",
+            key
+        )));
+    };
+
+    let sealed = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| CommandError::internal(format!("Failed to decode sealed value: {}", e)))?;
+    let plaintext = crypto::decrypt(vault_key, key.as_str().as_bytes(), &sealed)
+        .ok_or_else(|| CommandError::internal(format!("Failed to decrypt key '{}'", key)))?;
+
+    String::from_utf8(plaintext)
+        .map_err(|e| CommandError::internal(format!("Failed to decode value: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util::test_dir;
+    use crate::secure_storage::Backend;
+    use std::collections::BTreeMap;
+
+    fn key(name: &str) -> StorageKey {
+        StorageKey::parse(name).unwrap()
+    }
+
+    // In-memory store that can fail a single write
+    struct FlakyStore {
+        values: Mutex<BTreeMap<StorageKey, String>>,
+        writes_left: Mutex<usize>,
+    }
+
+    impl FlakyStore {
+        fn new(entries: &[(&str, &str)]) -> Self {
+            Self {
+                values: Mutex::new(
+                    entries
+                        .iter()
+                        .map(|(name, value)| (key(name), value.to_string()))
+                        .collect(),
+                ),
+                writes_left: Mutex::new(usize::MAX),
+            }
+        }
+
+        // Let `writes` more writes through, then fail the next one
+        fn fail_after(&self, writes: usize) {
+            *self.writes_left.lock().unwrap() = writes;
+        }
+
+        fn value(&self, name: &str) -> String {
+            self.values.lock().unwrap()[&key(name)].clone()
+        }
+    }
+
+    impl SecretStore for FlakyStore {
+        fn backend(&self) -> Backend {
+            Backend::File
+        }
+
+        fn get(&self, key: &StorageKey) -> Result<String, CommandError> {
+            self.values
+                .lock()
+                .unwrap()
+                .get(key)
+                .cloned()
+                .ok_or_else(|| CommandError::not_found("No such key"))
+        }
+
+        fn set(&self, key: &StorageKey, value: &str) -> Result<(), CommandError> {
+            let mut writes_left = self.writes_left.lock().unwrap();
+            if *writes_left == 0 {
+                *writes_left = usize::MAX;
+                return Err(CommandError::internal("Store is unavailable"));
+            }
+            *writes_left -= 1;
+            self.values
+                .lock()
+                .unwrap()
+                .insert(key.clone(), value.to_string());
+            Ok(())
+        }
+
+        fn remove(&self, key: &StorageKey) -> Result<(), CommandError> {
+            self.values.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn keys(&self) -> Result<Vec<StorageKey>, CommandError> {
+            Ok(self.values.lock().unwrap().keys().cloned().collect())
+        }
+    }
+
+    const ENTRIES: [(&str, &str); 3] = [
+        ("anthropic-api-key", "sk-ant"),
+        ("mistral-api-key", "sk-mis"),
+        ("openai-api-key", "sk-oai"),
+    ];
+
+    #[test]
+    fn failed_enable_keeps_values_readable() {
+        let dir = test_dir("vault-failed-enable");
+        let store = FlakyStore::new(&ENTRIES);
+        store.fail_after(2);
+
+        let vault = Vault::default();
+        assert!(vault.enable(&dir, &store, "correct horse", None).is_err());
+
+        assert!(!vault.status(&dir).unwrap().enabled);
+        assert!(!dir.join(VAULT_FILE).exists());
+        for (name, value) in ENTRIES {
+            assert_eq!(store.value(name), value);
+        }
+    }
+
+    #[test]
+    fn failed_disable_keeps_values_sealed() {
+        let dir = test_dir("vault-failed-disable");
+        let store = FlakyStore::new(&ENTRIES);
+        let vault = Vault::default();
+        vault.enable(&dir, &store, "correct horse", None).unwrap();
+        let sealed: Vec<String> = ENTRIES.iter().map(|(name, _)| store.value(name)).collect();
+
+        store.fail_after(1);
+        assert!(vault.disable(&dir, &store, "correct horse").is_err());
+
+        assert!(vault.status(&dir).unwrap().enabled);
+        for ((name, value), sealed) in ENTRIES.iter().zip(&sealed) {
+            assert_eq!(&store.value(name), sealed);
+            assert_eq!(vault.open(&dir, &key(name), sealed).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn sealed_values_round_trip() {
+        let vault_key = crypto::generate_key();
+        let sealed = seal_with(&vault_key, &key("openai-api-key"), "sk-one").unwrap();
+
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert_eq!(
+            open_with(&vault_key, &key("openai-api-key"), &sealed).unwrap(),
+            "sk-one"
+        );
+        // The key name is bound to the value
+        assert!(open_with(&vault_key, &key("other-key"), &sealed).is_err());
+    }
+
+    #[test]
+    fn unsealed_values_are_refused() {
+        let vault_key = crypto::generate_key();
+        let error = open_with(&vault_key, &key("openai-api-key"), "sk-planted").unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::Internal);
+        assert!(error.message.contains("not sealed"));
+    }
+}
@@ -0,0 +1,160 @@
+// Watches the source locale file and reports which keys changed
+//
+// The parent directory is watched rather than the file itself, because most
+// editors save by writing a new file and renaming it over the old one, which
+// ends a watch on the original file. Events are debounced, the file is parsed
+// again and compared with the previous version by key path, and a
+// `source-file-changed` event listing the added, removed and changed keys is
+// emitted. Saves that don't change any key (formatting, or a file that doesn't
+// parse yet) are ignored. If the file can't be parsed when the watch starts,
+// the first version that parses becomes the baseline and nothing is reported
+// for it. Only one file is watched at a time.
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::encoding;
+use crate::error::{CommandError, ErrorCode};
+use crate::formats::{self, Format};
+use crate::locale_json;
+
+pub const SOURCE_CHANGED_EVENT: &str = "source-file-changed";
+
+// How long events must stop arriving before the file is read again
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceChange {
+    path: PathBuf,
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<String>,
+}
+
+// Managed state owning the active watcher. Dropping the watcher stops the
+// thread that handles its events.
+#[derive(Default)]
+pub struct SourceWatcher {
+    active: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl SourceWatcher {
+    // Start watching `path`, replacing any previous watch
+    pub fn watch(&self, app: AppHandle, path: PathBuf) -> Result<(), CommandError> {
+        let directory = path
+            .parent()
+            .ok_or_else(|| CommandError::invalid_input("File has no parent directory"))?
+            .to_path_buf();
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| CommandError::invalid_input("File has no name"))?
+            .to_os_string();
+        let snapshot = read_json(&path);
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            if let Ok(event) = result {
+                let touches_file = event
+                    .paths
+                    .iter()
+                    .any(|changed| changed.file_name() == Some(file_name.as_os_str()));
+                if touches_file && !matches!(event.kind, EventKind::Access(_)) {
+                    let _ = tx.send(());
+                }
+            }
+        })
+        .map_err(watch_error)?;
+        watcher
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+
+        thread::spawn(move || watch_loop(app, path, snapshot, rx));
+
+        *self.lock()? = Some(watcher);
+        Ok(())
+    }
+
+    // Stop watching. Returns whether a file was being watched.
+    pub fn unwatch(&self) -> Result<bool, CommandError> {
+        Ok(self.lock()?.take().is_some())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Option<RecommendedWatcher>>, CommandError> {
+        self.active
+            .lock()
+            .map_err(|_| CommandError::internal("Source watcher is poisoned"))
+    }
+}
+
+fn watch_loop(app: AppHandle, path: PathBuf, mut snapshot: Option<Value>, rx: Receiver<()>) {
+    // Ends once the watcher (and with it the sender) is dropped
+    while rx.recv().is_ok() {
+        // Editors often save in several steps; wait for the events to settle
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+        let current = match read_json(&path) {
+            Some(current) => current,
+            None => continue,
+        };
+
+        // Without a valid previous version there is nothing to compare with
+        let Some(previous) = &snapshot else {
+            snapshot = Some(current);
+            continue;
+        };
+
+        let change = diff(&path, previous, &current);
+        if change.added.is_empty() && change.removed.is_empty() && change.changed.is_empty() {
+            continue;
+        }
+
+        let _ = app.emit(SOURCE_CHANGED_EVENT, &change);
+        snapshot = Some(current);
+    }
+}
+
+// The parsed file, or None while it is missing or not valid JSON
+fn read_json(path: &Path) -> Option<Value> {
+    let text = encoding::read_text(path).ok()?;
+    formats::load(Format::from_path(path), &text.content, text.encoding)
+        .ok()
+        .map(|loaded| loaded.data)
+}
+
+fn diff(path: &Path, old: &Value, new: &Value) -> SourceChange {
+    let old_leaves = locale_json::leaves(old);
+    let new_leaves = locale_json::leaves(new);
+
+    let mut change = SourceChange {
+        path: path.to_path_buf(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+
+    for (key, new_value) in &new_leaves {
+        match old_leaves.get(key) {
+            None => change.added.push(key.clone()),
+            Some(old_value) if old_value != new_value => change.changed.push(key.clone()),
+            Some(_) => {}
+        }
+    }
+    change.removed = old_leaves
+        .keys()
+        .filter(|key| !new_leaves.contains_key(*key))
+        .cloned()
+        .collect();
+
+    change
+}
+
+fn watch_error(error: notify::Error) -> CommandError {
+    CommandError::new(ErrorCode::Io, format!("Failed to watch file: {}", error))
+}